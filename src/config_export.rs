@@ -0,0 +1,89 @@
+use super::*;
+use std::collections::BTreeMap;
+
+/// Everything the user has customized, in one file to move to another machine
+#[derive(Serialize, Deserialize)]
+struct ConfigExport {
+    config: serde_json::Value,
+    #[serde(default)]
+    versions: BTreeMap<String, serde_json::Value>,
+}
+
+pub fn export(path: &std::path::Path, config: &LauncherConfig) -> Result<(), String> {
+    let mut versions = BTreeMap::new();
+    if let Ok(dir) = std::fs::read_dir(utils::get_versions_path()) {
+        for entry in dir.flatten() {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let settings = VersionSettings::load_named(&name);
+            if settings != VersionSettings::default() {
+                versions.insert(
+                    name,
+                    serde_json::to_value(settings).map_err(|err| err.to_string())?,
+                );
+            }
+        }
+    }
+    // The token and the proxy password are #[serde(skip)], so they never end up in the file
+    let export = ConfigExport {
+        config: serde_json::to_value(config).map_err(|err| err.to_string())?,
+        versions,
+    };
+    let json = serde_json::to_string_pretty(&export).map_err(|err| err.to_string())?;
+    std::fs::write(path, json).map_err(|err| err.to_string())
+}
+
+/// Applies an exported file over `config`, returns the names of fields that were skipped as invalid.
+/// Paths, the window position and the secrets belong to this machine and are kept
+pub fn import(
+    path: &std::path::Path,
+    config: &mut LauncherConfig,
+    interface: &Arc<Interface>,
+) -> Result<Vec<String>, String> {
+    let text = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+    let export: ConfigExport = serde_json::from_str(&text).map_err(|err| err.to_string())?;
+    let (mut imported, mut skipped) = merge_valid(config, export.config);
+    imported.install_dir = config.install_dir.clone();
+    imported.lua_dir = config.lua_dir.clone();
+    imported.shared_content_dir = config.shared_content_dir.clone();
+    imported.window = config.window;
+    imported.banner_path = config.banner_path.clone();
+    imported.github_token = config.github_token.clone();
+    imported.proxy_password = config.proxy_password.clone();
+    *config = imported;
+
+    for (name, settings) in export.versions {
+        match serde_json::from_value::<VersionSettings>(settings) {
+            Ok(settings) => settings.save_named(&name, interface),
+            Err(_) => skipped.push(format!("settings of {}", name)),
+        }
+    }
+    Ok(skipped)
+}
+
+/// Takes every field of `imported` that deserializes, fields that don't keep their value from `base`
+fn merge_valid<T: Serialize + serde::de::DeserializeOwned + Clone>(
+    base: &T,
+    imported: serde_json::Value,
+) -> (T, Vec<String>) {
+    let (Ok(serde_json::Value::Object(mut merged)), serde_json::Value::Object(imported)) =
+        (serde_json::to_value(base), imported)
+    else {
+        return (base.clone(), vec!["config".to_owned()]);
+    };
+    let mut skipped = Vec::new();
+    for (key, value) in imported {
+        if !merged.contains_key(&key) {
+            // Unknown, e.g. from a newer launcher
+            continue;
+        }
+        let previous = merged.insert(key.clone(), value);
+        if serde_json::from_value::<T>(serde_json::Value::Object(merged.clone())).is_err() {
+            if let Some(previous) = previous {
+                merged.insert(key.clone(), previous);
+            }
+            skipped.push(key);
+        }
+    }
+    let merged = serde_json::from_value(serde_json::Value::Object(merged)).unwrap_or(base.clone());
+    (merged, skipped)
+}
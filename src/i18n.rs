@@ -0,0 +1,76 @@
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU8, Ordering};
+
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+pub enum Language {
+    #[default]
+    English,
+    Russian,
+}
+
+impl Language {
+    pub const ALL: [Language; 2] = [Self::English, Self::Russian];
+
+    /// In its own language, that's what people look for in the selector
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::English => "English",
+            Self::Russian => "Русский",
+        }
+    }
+}
+
+static LANGUAGE: AtomicU8 = AtomicU8::new(Language::English as u8);
+
+pub fn set_language(language: Language) {
+    LANGUAGE.store(language as u8, Ordering::Relaxed);
+}
+
+pub fn language() -> Language {
+    Language::ALL
+        .into_iter()
+        .find(|language| *language as u8 == LANGUAGE.load(Ordering::Relaxed))
+        .unwrap_or_default()
+}
+
+/// Translates an English string, missing translations stay in English
+pub fn tr(text: &'static str) -> &'static str {
+    match language() {
+        Language::English => None,
+        Language::Russian => russian(text),
+    }
+    .unwrap_or(text)
+}
+
+fn russian(text: &str) -> Option<&'static str> {
+    Some(match text {
+        "Version:" => "Версия:",
+        "Play" => "Играть",
+        "Stop" => "Остановить",
+        "Force refresh" => "Переустановить",
+        "Settings" => "Настройки",
+        "Language: " => "Язык: ",
+        "Theme: " => "Тема: ",
+        "Installed only" => "Только установленные",
+        "Launch arguments" => "Аргументы запуска",
+        "Cancel" => "Отмена",
+        "Close" => "Закрыть",
+        "Delete" => "Удалить",
+        "Ok" => "Ок",
+        "About" => "О программе",
+        "Copy log" => "Копировать лог",
+        "Copy all errors" => "Копировать все ошибки",
+        "Copy" => "Копировать",
+        "No version selected" => "Версия не выбрана",
+        "Running the game" => "Запуск игры",
+        "The game is already running" => "Игра уже запущена",
+        "Cancelled" => "Отменено",
+        "Failed to run command!" => "Не удалось выполнить команду!",
+        "Error: Binary not found! Use force-refresh" => {
+            "Ошибка: исполняемый файл не найден! Используйте переустановку"
+        }
+        _ => return None,
+    })
+}
@@ -0,0 +1,225 @@
+use super::*;
+
+/// Where the messages and progress of an `Interface` go
+pub trait OutputSink: Send + Sync {
+    fn info(&self, message: &str);
+    fn warning(&self, message: &str);
+    fn error(&self, message: &str);
+    /// Lines only meant for the log, like the output of commands
+    fn log(&self, level: LogLevel, message: &str);
+    fn progress(&self, event: &ProgressEvent);
+    fn show(&self, _ctx: &egui::Context) {}
+}
+
+/// Toasts, and the lines of the log window
+pub struct GuiSink {
+    toasts: Mutex<egui_notify::Toasts>,
+    log: Arc<Mutex<Vec<LogLine>>>,
+}
+
+impl GuiSink {
+    pub fn new(log: Arc<Mutex<Vec<LogLine>>>) -> Self {
+        Self {
+            toasts: Mutex::new(egui_notify::Toasts::default()),
+            log,
+        }
+    }
+}
+
+impl OutputSink for GuiSink {
+    fn info(&self, message: &str) {
+        self.toasts.lock_safe().info(message);
+        self.log(LogLevel::Info, message);
+    }
+
+    fn warning(&self, message: &str) {
+        self.toasts.lock_safe().warning(message);
+        self.log(LogLevel::Warning, message);
+    }
+
+    fn error(&self, message: &str) {
+        self.toasts.lock_safe().error(message);
+        self.log(LogLevel::Error, message);
+    }
+
+    fn log(&self, level: LogLevel, message: &str) {
+        self.log.lock_safe().push(LogLine {
+            level,
+            text: message.to_owned(),
+        });
+    }
+
+    fn progress(&self, _event: &ProgressEvent) {}
+
+    fn show(&self, ctx: &egui::Context) {
+        self.toasts.lock_safe().show(ctx);
+    }
+}
+
+/// Plain text for the terminal, warnings and errors go to stderr
+pub struct TextSink;
+
+impl OutputSink for TextSink {
+    fn info(&self, message: &str) {
+        self.log(LogLevel::Info, message);
+    }
+
+    fn warning(&self, message: &str) {
+        self.log(LogLevel::Warning, message);
+    }
+
+    fn error(&self, message: &str) {
+        self.log(LogLevel::Error, message);
+    }
+
+    fn log(&self, level: LogLevel, message: &str) {
+        if matches!(level, LogLevel::Warning | LogLevel::Error) {
+            eprintln!("{}", message);
+        } else {
+            println!("{}", message);
+        }
+    }
+
+    fn progress(&self, _event: &ProgressEvent) {}
+}
+
+/// One JSON object per line on stdout, for scripts driving the launcher
+pub struct JsonSink;
+
+impl JsonSink {
+    fn emit(&self, value: serde_json::Value) {
+        use std::io::Write;
+        let mut stdout = std::io::stdout().lock();
+        writeln!(stdout, "{}", value).ok();
+        stdout.flush().ok();
+    }
+}
+
+impl OutputSink for JsonSink {
+    fn info(&self, message: &str) {
+        self.log(LogLevel::Info, message);
+    }
+
+    fn warning(&self, message: &str) {
+        self.log(LogLevel::Warning, message);
+    }
+
+    fn error(&self, message: &str) {
+        self.log(LogLevel::Error, message);
+    }
+
+    fn log(&self, level: LogLevel, message: &str) {
+        let level = match level {
+            LogLevel::Output => "output",
+            LogLevel::Info => "info",
+            LogLevel::Warning => "warning",
+            LogLevel::Error => "error",
+        };
+        self.emit(serde_json::json!({ "type": "log", "level": level, "msg": message }));
+    }
+
+    fn progress(&self, event: &ProgressEvent) {
+        self.emit(match event {
+            ProgressEvent::Started => serde_json::json!({ "type": "started" }),
+            ProgressEvent::Progress { done, total } => {
+                serde_json::json!({ "type": "progress", "done": done, "total": total })
+            }
+            ProgressEvent::Message(label) => serde_json::json!({ "type": "status", "msg": label }),
+            ProgressEvent::Finished => serde_json::json!({ "type": "finished", "success": true }),
+            ProgressEvent::Failed => serde_json::json!({ "type": "finished", "success": false }),
+        });
+    }
+}
+
+/// Records everything, for driving an `Interface` without a window or a terminal in tests.
+/// Clones share the records, keep one to inspect them
+#[cfg(test)]
+#[derive(Clone, Default)]
+pub struct TestSink {
+    pub messages: Arc<Mutex<Vec<LogLine>>>,
+    pub events: Arc<Mutex<Vec<ProgressEvent>>>,
+}
+
+#[cfg(test)]
+impl TestSink {
+    /// A headless `Interface` with the default settings, recording into the returned sink
+    pub fn interface(runtime: tokio::runtime::Handle) -> (Arc<Interface>, Self) {
+        utils::set_test_install_dir();
+        let sink = Self::default();
+        let interface =
+            Interface::headless(LauncherConfig::default(), Box::new(sink.clone()), runtime);
+        (Arc::new(interface), sink)
+    }
+
+    pub fn messages_at(&self, level: LogLevel) -> Vec<String> {
+        self.messages
+            .lock_safe()
+            .iter()
+            .filter(|line| line.level == level)
+            .map(|line| line.text.clone())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+impl OutputSink for TestSink {
+    fn info(&self, message: &str) {
+        self.log(LogLevel::Info, message);
+    }
+
+    fn warning(&self, message: &str) {
+        self.log(LogLevel::Warning, message);
+    }
+
+    fn error(&self, message: &str) {
+        self.log(LogLevel::Error, message);
+    }
+
+    fn log(&self, level: LogLevel, message: &str) {
+        self.messages.lock_safe().push(LogLine {
+            level,
+            text: message.to_owned(),
+        });
+    }
+
+    fn progress(&self, event: &ProgressEvent) {
+        self.events.lock_safe().push(event.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sink_records_messages() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let (interface, sink) = TestSink::interface(runtime.handle().clone());
+        interface.info("Downloading version binary");
+        interface.warning("  trimmed\n");
+        interface.report(&LaunchError::MissingTools(vec![
+            "git".to_owned(),
+            "cmake".to_owned(),
+        ]));
+        interface.report(&LaunchError::Cancelled);
+        interface.set_progress(0.5, "half");
+        interface.end_progress(false);
+
+        assert_eq!(
+            sink.messages_at(LogLevel::Info),
+            ["Downloading version binary"]
+        );
+        assert_eq!(
+            sink.messages_at(LogLevel::Warning),
+            ["trimmed", "Cancelled"]
+        );
+        let errors = sink.messages_at(LogLevel::Error);
+        assert_eq!(errors.len(), 2, "{:?}", errors);
+        assert!(errors[0].starts_with("git is required"), "{:?}", errors);
+        assert!(errors[1].starts_with("cmake is required"), "{:?}", errors);
+        let events = sink.events.lock_safe().clone();
+        assert_eq!(events.first(), Some(&ProgressEvent::Started));
+        assert!(events.contains(&ProgressEvent::Message("half".to_owned())));
+        assert_eq!(events.last(), Some(&ProgressEvent::Failed));
+    }
+}
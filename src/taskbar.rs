@@ -0,0 +1,77 @@
+use super::*;
+
+/// Mirrors the progress bar on the Windows taskbar icon, does nothing elsewhere
+#[derive(Default)]
+pub struct Taskbar {
+    failed: Arc<AtomicBool>,
+    last: Option<(Option<u64>, bool)>,
+    #[cfg(windows)]
+    list: Option<(
+        windows::Win32::UI::Shell::ITaskbarList3,
+        windows::Win32::Foundation::HWND,
+    )>,
+}
+
+impl Taskbar {
+    pub fn new(interface: &Interface) -> Self {
+        let this = Self::default();
+        let failed = this.failed.clone();
+        interface.subscribe(move |event| match event {
+            ProgressEvent::Started => failed.store(false, Ordering::Relaxed),
+            ProgressEvent::Failed => failed.store(true, Ordering::Relaxed),
+            _ => (),
+        });
+        this
+    }
+
+    pub fn update(&mut self, frame: &eframe::Frame, progress: Option<f32>) {
+        let state = (
+            progress.map(|progress| (progress.clamp(0.0, 1.0) * 1000.0) as u64),
+            self.failed.load(Ordering::Relaxed),
+        );
+        if self.last == Some(state) {
+            return;
+        }
+        self.last = Some(state);
+        #[cfg(windows)]
+        self.set(frame, state);
+        #[cfg(not(windows))]
+        let _ = frame;
+    }
+
+    #[cfg(windows)]
+    fn set(&mut self, frame: &eframe::Frame, (progress, failed): (Option<u64>, bool)) {
+        use raw_window_handle::{HasWindowHandle, RawWindowHandle};
+        use windows::Win32::{Foundation::HWND, System::Com::*, UI::Shell::*};
+        if self.list.is_none() {
+            let Ok(RawWindowHandle::Win32(handle)) =
+                frame.window_handle().map(|handle| handle.as_raw())
+            else {
+                return;
+            };
+            // Safety: plain COM calls on the UI thread, winit already initialized COM there
+            self.list = unsafe {
+                CoInitializeEx(None, COINIT_APARTMENTTHREADED).ok();
+                CoCreateInstance::<_, ITaskbarList3>(&TaskbarList, None, CLSCTX_INPROC_SERVER)
+                    .ok()
+                    .filter(|list| list.HrInit().is_ok())
+            }
+            .map(|list| (list, HWND(handle.hwnd.get())));
+        }
+        let Some((list, hwnd)) = &self.list else {
+            return;
+        };
+        let state = match (progress, failed) {
+            (_, true) => TBPF_ERROR,
+            (Some(_), false) => TBPF_NORMAL,
+            (None, false) => TBPF_NOPROGRESS,
+        };
+        // Safety: the window outlives the launcher app
+        unsafe {
+            list.SetProgressState(*hwnd, state).ok();
+            if let Some(progress) = progress.or(failed.then_some(1000)) {
+                list.SetProgressValue(*hwnd, progress, 1000).ok();
+            }
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use super::*;
+
+#[derive(Clone, Debug)]
+pub struct Update {
+    pub tag: String,
+    pub url: String,
+}
+
+pub async fn check(interface: Arc<Interface>) -> Option<Update> {
+    let release = match utils::github(&interface)
+        .repos("InfiniteCoder01", "voxel-engine-cpp-launcher")
+        .releases()
+        .get_latest()
+        .await
+    {
+        Ok(release) => release,
+        Err(err) => {
+            utils::check_unauthorized(&err, &interface);
+            log::warn!("Failed to check for launcher updates: {}", err);
+            return None;
+        }
+    };
+
+    let latest = version_manager::version_key(&release.tag_name)?;
+    let current = version_manager::version_key(env!("CARGO_PKG_VERSION"));
+    if current.is_some_and(|current| current >= latest) {
+        return None;
+    }
+
+    let Some(asset) = utils::find_platform_asset(&release.assets, &interface) else {
+        interface.info(format!(
+            "Launcher {} is available, but has no build for your platform",
+            release.tag_name
+        ));
+        return None;
+    };
+    interface.info(format!("Launcher {} is available", release.tag_name));
+    Some(Update {
+        tag: release.tag_name,
+        url: asset.browser_download_url.to_string(),
+    })
+}
+
+pub async fn apply(update: Update, interface: Arc<Interface>) {
+    let current = match std::env::current_exe() {
+        Ok(current) => current,
+        Err(err) => {
+            interface.error(format!("Failed to locate the launcher executable: {}", err));
+            return;
+        }
+    };
+    const PACKAGED: &str = "This update is packaged as an archive, please install it manually";
+    if utils::packaged_url(&update.url) {
+        interface.error(PACKAGED);
+        return;
+    }
+
+    interface.info(format!("Downloading launcher {}", update.tag));
+    let new = current.with_extension("new");
+    if let Err(err) = utils::download(&update.url, &new, &interface, "launcher").await {
+        interface.end_progress(false);
+        interface.report(&err);
+        return;
+    }
+    interface.end_progress(true);
+    // Only a bare executable can replace this one
+    match utils::is_packaged(&new, &update.url) {
+        Ok(false) => (),
+        Ok(true) => {
+            std::fs::remove_file(&new).ok();
+            interface.error(PACKAGED);
+            return;
+        }
+        Err(err) => {
+            interface.error(format!("Failed to read the downloaded update: {}", err));
+            return;
+        }
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&new, std::fs::Permissions::from_mode(0o755)).ok();
+    }
+
+    // Windows can't overwrite a running executable, but it can rename it
+    let result = if cfg!(windows) {
+        let old = current.with_extension("old");
+        std::fs::rename(&current, &old).and_then(|_| {
+            std::fs::rename(&new, &current).inspect_err(|_| {
+                // Without this there would be no launcher executable left
+                std::fs::rename(&old, &current).ok();
+            })
+        })
+    } else {
+        std::fs::rename(&new, &current)
+    };
+    if let Err(err) = result {
+        interface.error(format!(
+            "Failed to replace the launcher executable: {}",
+            err
+        ));
+        return;
+    }
+
+    interface.info("Restarting the launcher");
+    match std::process::Command::new(&current)
+        .args(std::env::args_os().skip(1))
+        .spawn()
+    {
+        Ok(_) => std::process::exit(0),
+        Err(err) => interface.error(format!("Failed to restart the launcher: {}", err)),
+    }
+}
+
+pub fn cleanup() {
+    if let Ok(current) = std::env::current_exe() {
+        std::fs::remove_file(current.with_extension("old")).ok();
+    }
+}
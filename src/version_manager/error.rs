@@ -0,0 +1,140 @@
+use super::*;
+
+/// Why installing or launching a version failed, `Interface::report` shows it to the user
+pub enum LaunchError {
+    Cancelled,
+    MissingTools(Vec<String>),
+    /// The version isn't available without downloading it
+    Offline,
+    DiskSpace {
+        needed: u64,
+        available: u64,
+    },
+    Download {
+        name: String,
+        error: utils::DownloadError,
+        /// A proxy is configured, so a failed connection may be its fault
+        proxied: bool,
+    },
+    ChecksumMismatch {
+        file: String,
+        expected: String,
+        actual: String,
+    },
+    /// Checksums or the signature couldn't be checked, or the signature is wrong
+    Verification(String),
+    Unpack(String),
+    /// The command couldn't be started or waited for
+    Command {
+        command: String,
+        error: std::io::Error,
+    },
+    CommandFailed {
+        /// The whole command line
+        command: String,
+        code: Option<i32>,
+    },
+    /// Neither downloadable nor buildable with the current settings
+    Unsupported(String),
+    NotFound(NotFoundReason),
+    GameRunning,
+    /// The recorded executable, if there is one
+    BinaryNotFound(Option<std::path::PathBuf>),
+    Game(std::io::Error),
+    Other(String),
+}
+
+impl LaunchError {
+    pub fn download(name: &str, error: utils::DownloadError, interface: &Interface) -> Self {
+        Self::Download {
+            name: name.to_owned(),
+            error,
+            proxied: utils::proxied(interface),
+        }
+    }
+}
+
+impl std::fmt::Display for LaunchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Cancelled => f.write_str(tr("Cancelled")),
+            Self::MissingTools(tools) => {
+                let tools = tools
+                    .iter()
+                    .map(|tool| {
+                        format!(
+                            "{} is required but was not found in PATH. {}",
+                            tool,
+                            utils::install_hint(tool)
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                f.write_str(&tools.join("\n"))
+            }
+            Self::Offline => {
+                f.write_str("This version has to be downloaded, turn off offline mode first")
+            }
+            Self::DiskSpace { needed, available } => write!(
+                f,
+                "Not enough disk space: {} needed, {} available",
+                utils::format_size(*needed),
+                utils::format_size(*available)
+            ),
+            Self::Download {
+                name,
+                error: utils::DownloadError::Timeout(seconds),
+                ..
+            } => write!(
+                f,
+                "Download of {} timed out, no data received for {} seconds",
+                name, seconds
+            ),
+            Self::Download {
+                name,
+                error: utils::DownloadError::Network(err),
+                proxied: true,
+            } if err.is_connect() => write!(
+                f,
+                "Failed to download {}: {} (check the proxy settings)",
+                name, err
+            ),
+            Self::Download { name, error, .. } => {
+                write!(f, "Failed to download {}: {}", name, error)
+            }
+            Self::ChecksumMismatch {
+                file,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "Checksum mismatch for {}: expected {}, got {}",
+                file, expected, actual
+            ),
+            Self::Verification(message) | Self::Unsupported(message) | Self::Other(message) => {
+                f.write_str(message)
+            }
+            Self::Unpack(message) => write!(f, "Failed to unpack version sources: {}", message),
+            Self::Command { command, error } => {
+                write!(f, "Failed to run command {:?}: {}", command, error)
+            }
+            Self::CommandFailed { command, code } => {
+                write!(f, "{} {:?}", tr("Failed to run command!"), command)?;
+                match code {
+                    Some(code) => write!(f, " (exit code {})", code),
+                    None => Ok(()),
+                }
+            }
+            Self::NotFound(reason) => write!(f, "Version files not found, {}", reason),
+            Self::GameRunning => f.write_str(tr("The game is already running")),
+            Self::BinaryNotFound(None) => {
+                f.write_str(tr("Error: Binary not found! Use force-refresh"))
+            }
+            Self::BinaryNotFound(Some(binary)) => write!(
+                f,
+                "Error: {:?} not found and no other game executable was found! Use force-refresh",
+                binary
+            ),
+            Self::Game(err) => write!(f, "Failed to run game executable: {}", err),
+        }
+    }
+}
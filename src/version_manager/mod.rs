@@ -5,39 +5,73 @@ use std::{
     sync::{Arc, Mutex},
 };
 
+pub mod error;
+pub mod notes;
+pub mod settings;
+pub mod toolchain;
 pub mod utils;
 pub mod version;
-pub use version::{Version, VersionData};
+pub use error::LaunchError;
+pub use notes::UserNotes;
+pub use settings::VersionSettings;
+pub use toolchain::{ConfigureInputs, Toolchain};
+pub use version::{NotFoundReason, Version, VersionData, USER_DATA};
 
 pub struct VersionManager {
     interface: Arc<Interface>,
 
     pub versions: Arc<Mutex<Vec<Arc<Version>>>>,
+    /// Bumped every time the list is rebuilt, the old `Version`s are stale after that
+    pub generation: Arc<std::sync::atomic::AtomicUsize>,
+    /// Directories left behind by an interrupted install, see `is_broken`
+    pub broken: Arc<Mutex<Vec<String>>>,
 }
 
 impl VersionManager {
     pub fn new(interface: Arc<Interface>) -> VersionManager {
-        let this = Self {
+        let this = Self::empty(interface);
+        this.update(false);
+        this
+    }
+
+    pub fn empty(interface: Arc<Interface>) -> VersionManager {
+        Self {
             interface,
 
             versions: Arc::new(Mutex::new(Vec::new())),
-        };
-        this.update();
-        this
+            generation: Arc::new(std::sync::atomic::AtomicUsize::new(0)),
+            broken: Arc::new(Mutex::new(Vec::new())),
+        }
     }
 
-    pub fn update(&self) {
+    /// Refreshes the version list, `force` bypasses the release cache
+    pub fn update(&self, force: bool) {
+        self.interface.spawn(self.fetch(force));
+    }
+
+    pub fn fetch(&self, force: bool) -> impl Future<Output = ()> + Send + 'static {
         let versions = self.versions.clone();
+        let generation = self.generation.clone();
+        let broken = self.broken.clone();
         let interface = self.interface.clone();
-        utils::spawn(async move {
-            *versions.lock().unwrap() = match octocrab::instance()
-                .repos("MihailRis", "VoxelEngine-Cpp")
-                .releases()
-                .list()
-                .send()
-                .await
-            {
-                Ok(versions) => versions
+        async move {
+            let remote = fetch_releases(&interface, force);
+            let local = {
+                let interface = interface.clone();
+                async move {
+                    tokio::task::spawn_blocking(move || scan_local_versions(&interface))
+                        .await
+                        .unwrap_or_default()
+                }
+            };
+            let (remote, (local, broken_names)) = tokio::join!(remote, local);
+            // An install in progress looks just like an interrupted one
+            if interface.progress().is_none() {
+                *broken.lock_safe() = broken_names;
+            }
+
+            let mut merged = match remote {
+                Ok(releases) => releases
                     .into_iter()
                     .filter_map(|release| {
                         Some(Arc::new(Version::parse(release, interface.clone())?))
@@ -48,53 +82,342 @@ impl VersionManager {
                         "Failed to fetch versions from github: {}",
                         err.to_string().split('\n').next().unwrap()
                     ));
-                    let mut local_versions = Vec::new();
-                    if let Ok(dir) = std::fs::read_dir(utils::get_versions_path()) {
-                        for local_version in dir.flatten() {
-                            let name = local_version.file_name();
-                            let name = name.to_string_lossy();
-                            let name = name.as_ref();
-                            let verfilepath = utils::get_version_path(name).join("version.ron");
-                            if verfilepath.exists() {
-                                match ron::from_str::<VersionData>(
-                                    &std::fs::read_to_string(verfilepath).unwrap(),
-                                ) {
-                                    Ok(version_data) => {
-                                        local_versions.push(Arc::new(Version {
-                                            name: name.to_string(),
-                                            data: Arc::new(Mutex::new(version_data)),
-                                        }));
-                                    }
-                                    Err(err) => {
-                                        interface.warning(format!(
-                                            "Corrupted version {:?}: {}",
-                                            name, err
-                                        ));
-                                        continue;
-                                    }
-                                }
-                            }
-                        }
-                    }
-                    local_versions
+                    Vec::new()
                 }
             };
-            versions.lock().unwrap().insert(
+            // Remote versions already pick up local version.ron files and carry release notes
+            for version in local {
+                if !merged.iter().any(|remote| remote.name == version.name) {
+                    merged.push(version);
+                }
+            }
+
+            sort_versions(&mut merged);
+            merged.insert(
                 0,
-                Arc::new(Version {
-                    name: "Latest (Git)".to_owned(),
-                    data: Arc::new(Mutex::new(VersionData::GitLatest)),
-                }),
+                Arc::new(Version::new("Latest (Git)", VersionData::GitLatest)),
             );
+            *versions.lock_safe() = merged;
+            generation.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        }
+    }
+
+    /// Installs the versions one after another, a failure doesn't stop the rest of the queue
+    pub fn install_batch(&self, versions: Vec<Arc<Version>>) {
+        let interface = self.interface.clone();
+        self.interface.spawn(async move {
+            let total = versions.len();
+            let mut failed = Vec::new();
+            let mut done = 0;
+            for (index, version) in versions.iter().enumerate() {
+                interface.set_queue(Some((index + 1, total)));
+                interface.info(format!(
+                    "Installing {}/{}: {}",
+                    index + 1,
+                    total,
+                    version.name
+                ));
+                if let Err(err) = version
+                    .as_ref()
+                    .clone()
+                    .install(interface.clone(), false)
+                    .await
+                {
+                    interface.report(&err);
+                    failed.push(version.name.clone());
+                }
+                done += 1;
+                if interface.cancelled() {
+                    break;
+                }
+            }
+            interface.set_queue(None);
+            if done < total {
+                interface.warning(format!(
+                    "Installation queue cancelled, {} versions were skipped",
+                    total - done
+                ));
+            }
+            if failed.is_empty() {
+                interface.info(format!("Installed {} versions", done));
+            } else {
+                interface.warning(format!(
+                    "Installed {} of {} versions, failed: {}",
+                    done - failed.len(),
+                    done,
+                    failed.join(", ")
+                ));
+            }
         });
     }
 
     pub fn try_find(&self, name: &str) -> Option<Arc<Version>> {
         self.versions
-            .lock()
-            .unwrap()
+            .lock_safe()
             .iter()
             .find(|version| version.name == name)
             .cloned()
     }
 }
+
+#[derive(Serialize, Deserialize)]
+struct ReleaseCache {
+    timestamp: u64,
+    repo: (String, String),
+    releases: Vec<octocrab::models::repos::Release>,
+}
+
+impl ReleaseCache {
+    fn path() -> std::path::PathBuf {
+        utils::get_install_dir().join("releases.json")
+    }
+
+    fn load() -> Option<Self> {
+        serde_json::from_str(&std::fs::read_to_string(Self::path()).ok()?).ok()
+    }
+
+    fn save(repo: (String, String), releases: &[octocrab::models::repos::Release]) {
+        let cache = ReleaseCache {
+            timestamp: utils::unix_time(),
+            repo,
+            releases: releases.to_vec(),
+        };
+        std::fs::create_dir_all(utils::get_install_dir()).ok();
+        if let Ok(cache) = serde_json::to_string(&cache) {
+            std::fs::write(Self::path(), cache).ok();
+        }
+    }
+
+    fn age_minutes(&self) -> u64 {
+        utils::unix_time().saturating_sub(self.timestamp) / 60
+    }
+}
+
+async fn fetch_releases(
+    interface: &Arc<Interface>,
+    force: bool,
+) -> octocrab::Result<Vec<octocrab::models::repos::Release>> {
+    if interface.config().offline_mode {
+        return Ok(Vec::new());
+    }
+    let repo = interface.config().repo();
+    let cache = ReleaseCache::load().filter(|cache| cache.repo == repo);
+    let ttl = interface.config().release_cache_minutes;
+    if let Some(cache) = cache
+        .as_ref()
+        .filter(|cache| !force && cache.age_minutes() < ttl)
+    {
+        interface.push_log(
+            &format!(
+                "Using release list from {} minutes ago, it will be refreshed in {} minutes",
+                cache.age_minutes(),
+                ttl - cache.age_minutes()
+            ),
+            LogLevel::Info,
+        );
+        return Ok(cache.releases.clone());
+    }
+
+    let result = fetch_release_pages(interface, &repo).await;
+    match result {
+        Ok(releases) => {
+            ReleaseCache::save(repo, &releases);
+            Ok(releases)
+        }
+        Err(octocrab::Error::GitHub { source, .. })
+            if source.message.contains("rate limit") && cache.is_some() =>
+        {
+            let cache = cache.unwrap();
+            interface.warning(format!(
+                "GitHub rate limit reached, using the release list from {} minutes ago (stale after {} minutes)",
+                cache.age_minutes(),
+                ttl
+            ));
+            Ok(cache.releases)
+        }
+        Err(err) => {
+            utils::check_unauthorized(&err, interface);
+            Err(err)
+        }
+    }
+}
+
+async fn fetch_release_pages(
+    interface: &Arc<Interface>,
+    repo: &(String, String),
+) -> octocrab::Result<Vec<octocrab::models::repos::Release>> {
+    const PER_PAGE: u8 = 100;
+    let pages = interface.config().release_pages.max(1);
+    // Don't take over the progress bar from a running task
+    let show_progress = interface.progress().is_none();
+    let mut shown = false;
+    let mut releases = Vec::new();
+    for page in 1..=pages {
+        if show_progress && page > 1 {
+            shown = true;
+            interface.set_progress(
+                (page - 1) as f32 / pages as f32,
+                format!("Fetching releases (page {})", page),
+            );
+        }
+        let result = utils::retry(
+            interface,
+            || async {
+                utils::github(interface)
+                    .repos(&repo.0, &repo.1)
+                    .releases()
+                    .list()
+                    .per_page(PER_PAGE)
+                    .page(page)
+                    .send()
+                    .await
+            },
+            |err| {
+                matches!(
+                    err,
+                    octocrab::Error::Hyper { .. } | octocrab::Error::Service { .. }
+                )
+            },
+        )
+        .await;
+        let page = match result {
+            Ok(page) => page,
+            Err(err) => {
+                if shown {
+                    interface.end_progress(false);
+                }
+                return Err(err);
+            }
+        };
+        let last = page.next.is_none() || page.items.len() < PER_PAGE as usize;
+        releases.extend(page.items);
+        if last {
+            break;
+        }
+    }
+    if shown {
+        interface.end_progress(true);
+    }
+    Ok(releases)
+}
+
+/// Files that a version without a version.ron can have without being broken
+const METADATA_FILES: [&str; 3] = ["settings.ron", "notes.ron", "cache"];
+
+/// What the user made rather than installed: settings, notes, saves and profiles. A failed
+/// reinstall that kept the user data leaves them behind
+fn is_user_content(name: &std::ffi::OsStr) -> bool {
+    ["settings.ron", "notes.ron", "profiles"]
+        .iter()
+        .chain(USER_DATA.iter())
+        .any(|kept| name == std::ffi::OsStr::new(kept))
+}
+
+/// Something was installed into the directory, but there is no version.ron to use it.
+/// Only settings, notes, user data or kept archives are fine, e.g. for a remote version that was
+/// configured before installing
+fn is_broken(path: &std::path::Path) -> bool {
+    std::fs::read_dir(path).is_ok_and(|dir| {
+        dir.flatten().any(|entry| {
+            let name = entry.file_name();
+            !is_user_content(&name)
+                && !METADATA_FILES
+                    .iter()
+                    .any(|metadata| name == std::ffi::OsStr::new(metadata))
+        })
+    })
+}
+
+/// Removes everything but the settings, notes and user data, the directory too if nothing is left
+pub fn clean_up(name: &str, interface: &Arc<Interface>) {
+    let path = utils::get_version_path(name);
+    let result = std::fs::read_dir(&path).and_then(|dir| {
+        for entry in dir.flatten() {
+            if is_user_content(&entry.file_name()) {
+                continue;
+            }
+            if entry.file_type()?.is_dir() {
+                std::fs::remove_dir_all(entry.path())?;
+            } else {
+                std::fs::remove_file(entry.path())?;
+            }
+        }
+        Ok(())
+    });
+    match result {
+        Ok(()) => {
+            std::fs::remove_dir(&path).ok();
+            interface.info(format!("Cleaned up {}", name));
+        }
+        Err(err) => interface.error(format!("Failed to clean up {}: {}", name, err)),
+    }
+}
+
+/// Installed versions, and the names of broken ones
+fn scan_local_versions(interface: &Arc<Interface>) -> (Vec<Arc<Version>>, Vec<String>) {
+    let mut local_versions = Vec::new();
+    let mut broken = Vec::new();
+    if let Ok(dir) = std::fs::read_dir(utils::get_versions_path()) {
+        for local_version in dir.flatten() {
+            let name = local_version.file_name();
+            let name = name.to_string_lossy();
+            let name = name.as_ref();
+            let verfilepath = utils::get_version_path(name).join("version.ron");
+            if verfilepath.exists() {
+                match VersionData::load(&verfilepath)
+                    .unwrap_or_else(|| Err("can't read version.ron".to_owned()))
+                {
+                    Ok(version_data) => {
+                        local_versions.push(Arc::new(Version::new(name, version_data)));
+                    }
+                    Err(err) => {
+                        let version = Version::new(name, VersionData::NotFound);
+                        let origin = if name == "Latest (Git)" {
+                            VersionData::GitLatest
+                        } else {
+                            VersionData::NotFound
+                        };
+                        if let Some(version_data) = version.recover(origin) {
+                            interface.info(format!("Repaired corrupted version {:?}", name));
+                            *version.data.lock_safe() = version_data;
+                            local_versions.push(Arc::new(version));
+                        } else {
+                            interface.warning(format!("Corrupted version {:?}: {}", name, err));
+                            broken.push(name.to_owned());
+                        }
+                    }
+                }
+            } else if local_version.path().is_dir()
+                // Lua, and the git checkout, which sync_repo repairs itself
+                && !name.starts_with('.')
+                && name != "Latest (Git)"
+                && is_broken(&local_version.path())
+            {
+                broken.push(name.to_owned());
+            }
+        }
+    }
+    (local_versions, broken)
+}
+
+/// Splits names like "v13.1-beta" into numeric parts and an optional pre-release suffix
+pub fn version_key(name: &str) -> Option<(Vec<u64>, bool, String)> {
+    let name = name.trim();
+    let name = name.strip_prefix(['v', 'V']).unwrap_or(name);
+    let (number, pre) = name.split_once(['-', ' ']).unwrap_or((name, ""));
+    let parts = number
+        .split('.')
+        .map(|part| part.parse::<u64>().ok())
+        .collect::<Option<Vec<_>>>()?;
+    Some((parts, pre.is_empty(), pre.to_owned()))
+}
+
+fn sort_versions(versions: &mut [Arc<Version>]) {
+    use std::cmp::Ordering;
+    versions.sort_by(|a, b| match (version_key(&a.name), version_key(&b.name)) {
+        (Some(a), Some(b)) => b.cmp(&a),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => a.name.cmp(&b.name),
+    });
+}
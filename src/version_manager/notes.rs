@@ -0,0 +1,63 @@
+use super::*;
+
+/// The user's own note and tags for a version, stored in versions/<name>/notes.ron
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UserNotes {
+    pub note: String,
+    pub tags: Vec<String>,
+}
+
+impl UserNotes {
+    pub fn load_named(name: &str) -> Self {
+        std::fs::read_to_string(utils::get_version_path(name).join("notes.ron"))
+            .ok()
+            .and_then(|notes| ron::from_str(&notes).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save_named(&self, name: &str, interface: &Arc<Interface>) {
+        let path = utils::get_version_path(name).join("notes.ron");
+        let result = if *self == Self::default() {
+            std::fs::remove_file(path).or_else(|err| match err.kind() {
+                std::io::ErrorKind::NotFound => Ok(()),
+                _ => Err(err),
+            })
+        } else {
+            std::fs::write(path, ron::to_string(self).unwrap())
+        };
+        if let Err(err) = result {
+            interface.error(format!("Failed to save notes: {}", err));
+        }
+    }
+
+    /// Returns true when something was edited
+    pub fn editor(&mut self, ui: &mut egui::Ui, id: impl std::hash::Hash) -> bool {
+        let mut changed = ui
+            .add(
+                egui::TextEdit::multiline(&mut self.note)
+                    .desired_rows(2)
+                    .hint_text("Note"),
+            )
+            .changed();
+        let id = egui::Id::new(("tags", id));
+        let mut text = ui.data_mut(|data| {
+            data.get_temp_mut_or_insert_with(id, || self.tags.join(", "))
+                .clone()
+        });
+        if ui
+            .add(egui::TextEdit::singleline(&mut text).hint_text("Tags, comma separated"))
+            .changed()
+        {
+            self.tags = text
+                .split(',')
+                .map(str::trim)
+                .filter(|tag| !tag.is_empty())
+                .map(str::to_owned)
+                .collect();
+            ui.data_mut(|data| data.insert_temp(id, text));
+            changed = true;
+        }
+        changed
+    }
+}
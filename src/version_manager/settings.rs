@@ -0,0 +1,204 @@
+use super::*;
+
+/// Per-version overrides of the global config, stored in versions/<name>/settings.ron
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VersionSettings {
+    pub launch_args: Option<String>,
+    pub cmake_extra_args: Option<Vec<String>>,
+    pub build_type: Option<BuildType>,
+    /// Name of the release asset picked when several match the platform
+    pub asset: Option<String>,
+    pub env: Option<Vec<(String, String)>>,
+    pub working_dir: Option<std::path::PathBuf>,
+    /// Opts out of the shared content folder
+    pub skip_shared_content: bool,
+    /// Isolated data directory in versions/<name>/profiles, the main one when None
+    pub profile: Option<String>,
+}
+
+impl VersionSettings {
+    pub fn load(version: &Version) -> Self {
+        Self::load_named(&version.name)
+    }
+
+    pub fn load_named(name: &str) -> Self {
+        std::fs::read_to_string(utils::get_version_path(name).join("settings.ron"))
+            .ok()
+            .and_then(|settings| ron::from_str(&settings).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, version: &Version, interface: &Arc<Interface>) {
+        self.save_named(&version.name, interface);
+    }
+
+    pub fn save_named(&self, name: &str, interface: &Arc<Interface>) {
+        let dir = utils::get_version_path(name);
+        let path = dir.join("settings.ron");
+        let result = if *self == Self::default() {
+            std::fs::remove_file(path).or_else(|err| match err.kind() {
+                std::io::ErrorKind::NotFound => Ok(()),
+                _ => Err(err),
+            })
+        } else {
+            std::fs::create_dir_all(&dir)
+                .and_then(|_| std::fs::write(path, ron::to_string(self).unwrap()))
+        };
+        if let Err(err) = result {
+            interface.error(format!("Failed to save version settings: {}", err));
+        }
+    }
+
+    pub fn launch_args(&self, config: &LauncherConfig) -> String {
+        self.launch_args
+            .clone()
+            .unwrap_or_else(|| config.launch_args.clone())
+    }
+
+    pub fn cmake_extra_args(&self, config: &LauncherConfig) -> Vec<String> {
+        match &self.cmake_extra_args {
+            Some(args) => args
+                .iter()
+                .map(|arg| arg.trim())
+                .filter(|arg| !arg.is_empty())
+                .map(str::to_owned)
+                .collect(),
+            None => config.cmake_extra_args().map(str::to_owned).collect(),
+        }
+    }
+
+    pub fn build_type(&self, config: &LauncherConfig) -> BuildType {
+        self.build_type.unwrap_or(config.build_type)
+    }
+
+    pub fn env(&self, config: &LauncherConfig) -> Vec<(String, String)> {
+        self.env.clone().unwrap_or_else(|| config.game_env.clone())
+    }
+
+    /// Relative paths are relative to the version folder
+    pub fn working_dir(&self, version: &Version, config: &LauncherConfig) -> std::path::PathBuf {
+        match self.working_dir.as_ref().or(config.working_dir.as_ref()) {
+            Some(dir) => version.path().join(dir),
+            None => version.path(),
+        }
+    }
+
+    /// Returns true when the window was closed
+    pub fn show(&mut self, ctx: &egui::Context, name: &str, config: &LauncherConfig) -> bool {
+        let mut close = false;
+        egui::Window::new(format!("Settings for {}", name))
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+            .default_size(vec2(500.0, 300.0))
+            .show(ctx, |ui| {
+                ui.label("Unchecked options use the global settings");
+
+                let mut launch_args = self.launch_args.is_some();
+                if ui.checkbox(&mut launch_args, "Launch arguments").changed() {
+                    self.launch_args = launch_args.then(|| config.launch_args.clone());
+                }
+                if let Some(launch_args) = &mut self.launch_args {
+                    ui.text_edit_singleline(launch_args);
+                }
+
+                let mut build_type = self.build_type.is_some();
+                if ui.checkbox(&mut build_type, "Build type").changed() {
+                    self.build_type = build_type.then_some(config.build_type);
+                }
+                if let Some(build_type) = &mut self.build_type {
+                    ComboBox::new("Version build type", "")
+                        .selected_text(build_type.as_str())
+                        .show_ui(ui, |ui| {
+                            for value in BuildType::ALL {
+                                ui.selectable_value(build_type, value, value.as_str());
+                            }
+                        });
+                }
+
+                let mut cmake_extra_args = self.cmake_extra_args.is_some();
+                if ui
+                    .checkbox(
+                        &mut cmake_extra_args,
+                        "Extra CMake arguments (one per line)",
+                    )
+                    .changed()
+                {
+                    self.cmake_extra_args =
+                        cmake_extra_args.then(|| config.cmake_extra_args.clone());
+                }
+                if let Some(args) = &mut self.cmake_extra_args {
+                    let mut text = args.join("\n");
+                    if ui
+                        .add(egui::TextEdit::multiline(&mut text).desired_rows(3))
+                        .changed()
+                    {
+                        *args = text.split('\n').map(str::to_owned).collect();
+                    }
+                }
+
+                let mut env = self.env.is_some();
+                if ui
+                    .checkbox(&mut env, "Environment variables (NAME=value per line)")
+                    .changed()
+                {
+                    self.env = env.then(|| config.game_env.clone());
+                }
+                if let Some(env) = &mut self.env {
+                    env_editor(ui, name, env);
+                }
+
+                let mut working_dir = self.working_dir.is_some();
+                if ui.checkbox(&mut working_dir, "Working directory").changed() {
+                    self.working_dir = working_dir
+                        .then(|| config.working_dir.clone().unwrap_or_else(|| ".".into()));
+                }
+                if let Some(working_dir) = &mut self.working_dir {
+                    let mut text = working_dir.to_string_lossy().into_owned();
+                    if ui.text_edit_singleline(&mut text).changed() {
+                        *working_dir = text.into();
+                    }
+                }
+
+                if config.shared_content_dir.is_some() {
+                    ui.checkbox(
+                        &mut self.skip_shared_content,
+                        "Don't use the shared content folder",
+                    );
+                }
+
+                ui.with_layout(Layout::right_to_left(Align::Max), |ui| {
+                    if ui.button("Save & Close").clicked() {
+                        close = true;
+                    }
+                });
+            });
+        close
+    }
+}
+
+/// Edits NAME=value lines, lines without '=' are kept in the editor but not applied
+pub fn env_editor(ui: &mut egui::Ui, id: impl std::hash::Hash, env: &mut Vec<(String, String)>) {
+    let id = egui::Id::new(("env", id));
+    let mut text = ui.data_mut(|data| {
+        data.get_temp_mut_or_insert_with(id, || {
+            env.iter()
+                .map(|(name, value)| format!("{}={}", name, value))
+                .collect::<Vec<_>>()
+                .join("\n")
+        })
+        .clone()
+    });
+    if ui
+        .add(egui::TextEdit::multiline(&mut text).desired_rows(3))
+        .changed()
+    {
+        *env = text
+            .lines()
+            .filter_map(|line| line.split_once('='))
+            .map(|(name, value)| (name.trim().to_owned(), value.to_owned()))
+            .filter(|(name, _)| !name.is_empty())
+            .collect();
+        ui.data_mut(|data| data.insert_temp(id, text));
+    }
+}
@@ -0,0 +1,207 @@
+use super::*;
+
+/// The compiler and generator a build directory was configured with, stored in
+/// versions/<name>/toolchain.ron. CMake can't switch them in an existing build directory
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Toolchain {
+    pub generator: Option<String>,
+    pub c_compiler: Option<String>,
+    pub cxx_compiler: Option<String>,
+}
+
+impl Toolchain {
+    /// From the cmake arguments and the environment, the same places cmake looks
+    pub fn current(cmake_args: &[String]) -> Self {
+        let define = |name: &str| {
+            let prefix = format!("-D{}", name);
+            cmake_args.iter().find_map(|arg| {
+                let value = arg.strip_prefix(&prefix)?;
+                // -DCMAKE_CXX_COMPILER=g++ or -DCMAKE_CXX_COMPILER:FILEPATH=g++
+                let (_, value) = value.split_once('=')?;
+                (!value.is_empty()).then(|| value.to_owned())
+            })
+        };
+        let env = |name: &str| std::env::var(name).ok().filter(|value| !value.is_empty());
+        let generator = cmake_args
+            .iter()
+            .position(|arg| arg == "-G")
+            .and_then(|index| cmake_args.get(index + 1).cloned())
+            .or_else(|| {
+                cmake_args
+                    .iter()
+                    .find_map(|arg| arg.strip_prefix("-G").filter(|value| !value.is_empty()))
+                    .map(str::to_owned)
+            })
+            .or_else(|| env("CMAKE_GENERATOR"));
+        let (default_c, default_cxx) = if cfg!(windows) {
+            ("cl", "cl")
+        } else {
+            ("cc", "c++")
+        };
+        Self {
+            generator,
+            c_compiler: resolve(define("CMAKE_C_COMPILER").or_else(|| env("CC")), default_c),
+            cxx_compiler: resolve(
+                define("CMAKE_CXX_COMPILER").or_else(|| env("CXX")),
+                default_cxx,
+            ),
+        }
+    }
+
+    fn path(version: &Version) -> std::path::PathBuf {
+        version.path().join("toolchain.ron")
+    }
+
+    pub fn load(version: &Version) -> Option<Self> {
+        ron::from_str(&std::fs::read_to_string(Self::path(version)).ok()?).ok()
+    }
+
+    pub fn save(&self, version: &Version) {
+        if let Ok(toolchain) = ron::to_string(self) {
+            std::fs::write(Self::path(version), toolchain).ok();
+        }
+    }
+
+    /// Why the existing build directory can't be reused, if it can't
+    pub fn mismatch(&self, version: &Version) -> Option<String> {
+        let cache =
+            std::fs::read_to_string(version.path().join("build").join("CMakeCache.txt")).ok()?;
+        if let Some(previous) = Self::load(version) {
+            if previous != *self {
+                return Some(format!(
+                    "the toolchain changed from {} to {}",
+                    previous, self
+                ));
+            }
+        }
+        // Also catches build directories from before the toolchain was recorded
+        let cached = |name: &str| {
+            cache.lines().find_map(|line| {
+                let (key, value) = line.split_once('=')?;
+                (key.split(':').next() == Some(name)).then(|| value.trim().to_owned())
+            })
+        };
+        if let (Some(generator), Some(cached)) = (&self.generator, cached("CMAKE_GENERATOR")) {
+            if *generator != cached {
+                return Some(format!(
+                    "it was configured with the {:?} generator, not {:?}",
+                    cached, generator
+                ));
+            }
+        }
+        let compilers = [
+            (&self.c_compiler, "CMAKE_C_COMPILER"),
+            (&self.cxx_compiler, "CMAKE_CXX_COMPILER"),
+        ];
+        for (compiler, key) in compilers {
+            if let (Some(compiler), Some(cached)) = (compiler, cached(key)) {
+                if !same_file(compiler, &cached) {
+                    return Some(format!(
+                        "it was configured with {:?}, not {:?}",
+                        cached, compiler
+                    ));
+                }
+            }
+        }
+        None
+    }
+}
+
+impl std::fmt::Display for Toolchain {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let show = |value: &Option<String>| value.clone().unwrap_or_else(|| "default".to_owned());
+        write!(
+            f,
+            "{} / {} / {}",
+            show(&self.generator),
+            show(&self.c_compiler),
+            show(&self.cxx_compiler)
+        )
+    }
+}
+
+/// Hash of what the last successful configure depended on, stored in build/configure.sha256 so
+/// a clean build directory forgets it. Unchanged inputs mean the configure can be skipped
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConfigureInputs(String);
+
+impl ConfigureInputs {
+    /// The cmake arguments and every CMakeLists.txt and *.cmake file of the sources
+    pub fn current(version: &Version, cmake_args: &[String]) -> Self {
+        use sha2::{Digest, Sha256};
+        let mut hasher = Sha256::new();
+        for arg in cmake_args {
+            hasher.update(arg.as_bytes());
+            hasher.update([0]);
+        }
+        let mut files = Vec::new();
+        let mut dirs = vec![version.path()];
+        while let Some(dir) = dirs.pop() {
+            let Ok(entries) = std::fs::read_dir(&dir) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let path = entry.path();
+                let name = entry.file_name().to_string_lossy().into_owned();
+                if path.is_dir() {
+                    if name != "build" && !name.starts_with('.') {
+                        dirs.push(path);
+                    }
+                } else if name == "CMakeLists.txt" || name.ends_with(".cmake") {
+                    files.push(path);
+                }
+            }
+        }
+        // Directory order differs between file systems
+        files.sort();
+        for file in files {
+            hasher.update(file.to_string_lossy().as_bytes());
+            hasher.update([0]);
+            hasher.update(std::fs::read(&file).unwrap_or_default());
+            hasher.update([0]);
+        }
+        Self(format!("{:x}", hasher.finalize()))
+    }
+
+    fn path(version: &Version) -> std::path::PathBuf {
+        version.path().join("build").join("configure.sha256")
+    }
+
+    pub fn save(&self, version: &Version) {
+        std::fs::write(Self::path(version), &self.0).ok();
+    }
+
+    /// Whether the build directory was configured with exactly these inputs
+    pub fn unchanged(&self, version: &Version) -> bool {
+        version
+            .path()
+            .join("build")
+            .join("CMakeCache.txt")
+            .is_file()
+            && std::fs::read_to_string(Self::path(version))
+                .is_ok_and(|previous| previous.trim() == self.0)
+    }
+}
+
+/// Full path of a compiler, so "g++" and "/usr/bin/g++" compare equal
+fn resolve(compiler: Option<String>, default: &str) -> Option<String> {
+    let compiler = compiler.unwrap_or_else(|| default.to_owned());
+    let path = std::path::Path::new(&compiler);
+    let path = if path.components().count() > 1 {
+        path.to_path_buf()
+    } else {
+        utils::find_in_path(&compiler)?
+    };
+    Some(
+        path.canonicalize()
+            .unwrap_or(path)
+            .to_string_lossy()
+            .into_owned(),
+    )
+}
+
+fn same_file(a: &str, b: &str) -> bool {
+    let canonical = |path: &str| std::path::Path::new(path).canonicalize().ok();
+    a == b || canonical(a).is_some_and(|a| Some(a) == canonical(b))
+}
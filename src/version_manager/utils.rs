@@ -2,106 +2,1483 @@ use super::*;
 use std::sync::Arc;
 use std::sync::Mutex;
 
-pub async fn download(url: &str, interface: &Arc<Interface>, name: &str) -> Option<Vec<u8>> {
-    let bytes = match reqwest::ClientBuilder::new()
+/// Locks a mutex, using its data even if another thread panicked while holding it,
+/// so that a failed background task can't bring down the UI
+pub trait LockExt<T> {
+    fn lock_safe(&self) -> std::sync::MutexGuard<'_, T>;
+}
+
+impl<T> LockExt<T> for Mutex<T> {
+    fn lock_safe(&self) -> std::sync::MutexGuard<'_, T> {
+        self.lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+pub enum DownloadError {
+    Network(reqwest::Error),
+    Io(std::io::Error),
+    /// No data for this many seconds
+    Timeout(u64),
+    /// The server answered with an error page, `body` is the start of it for text responses
+    Status {
+        status: reqwest::StatusCode,
+        body: String,
+    },
+}
+
+impl DownloadError {
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Self::Network(err) => err.is_connect() || err.is_timeout() || err.is_body(),
+            Self::Io(_) => false,
+            Self::Timeout(_) => true,
+            Self::Status { status, .. } => {
+                status.is_server_error()
+                    || *status == reqwest::StatusCode::TOO_MANY_REQUESTS
+                    || *status == reqwest::StatusCode::RANGE_NOT_SATISFIABLE
+            }
+        }
+    }
+}
+
+impl From<reqwest::Error> for DownloadError {
+    fn from(err: reqwest::Error) -> Self {
+        Self::Network(err)
+    }
+}
+
+impl From<std::io::Error> for DownloadError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl std::fmt::Display for DownloadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Network(err) => err.fmt(f),
+            Self::Io(err) => err.fmt(f),
+            Self::Timeout(seconds) => write!(f, "no data received for {} seconds", seconds),
+            Self::Status { status, body } if body.is_empty() => write!(f, "HTTP {}", status),
+            Self::Status { status, body } => write!(f, "HTTP {}: {}", status, body),
+        }
+    }
+}
+
+fn client(interface: &Interface) -> reqwest::Client {
+    let connect_timeout = interface.config().connect_timeout_secs.max(1);
+    let mut builder = reqwest::ClientBuilder::new()
         .user_agent("VoxelLauncherWGET/1.0")
-        .build()
-        .unwrap()
-        .get(url)
-        .send()
-        .await
-    {
-        Ok(mut response) => {
-            let download = || async move {
-                let mut bytes = Vec::new();
-                let mut progress = 0;
-                let content_length = response.content_length();
-                while let Some(chunk) = response.chunk().await? {
-                    bytes.extend_from_slice(&chunk);
-                    progress += chunk.len();
-                    if let Some(length) = content_length {
-                        interface.replace_progress(progress as f32 / length as f32);
-                    }
-                }
-                Ok(bytes)
+        .connect_timeout(std::time::Duration::from_secs(connect_timeout));
+    // Without configured proxies reqwest picks up HTTP_PROXY/HTTPS_PROXY by itself
+    let proxies = interface.config().proxies();
+    for (scheme, url) in proxies {
+        let proxy = url.and_then(|url| {
+            match scheme {
+                "http" => reqwest::Proxy::http(url),
+                _ => reqwest::Proxy::https(url),
+            }
+            .map_err(|err| err.to_string())
+        });
+        match proxy {
+            Ok(proxy) => builder = builder.proxy(proxy),
+            Err(err) => interface.warning(err),
+        }
+    }
+    builder.build().unwrap()
+}
+
+/// Environment for child processes, so git and cmake go through the same proxies
+pub fn proxy_env(interface: &Interface) -> Vec<(&'static str, String)> {
+    let proxies = interface.config().proxies();
+    let mut env = Vec::new();
+    for (scheme, url) in proxies {
+        if let Ok(url) = url {
+            let (lower, upper) = match scheme {
+                "http" => ("http_proxy", "HTTP_PROXY"),
+                _ => ("https_proxy", "HTTPS_PROXY"),
             };
-            download().await
+            env.push((lower, url.to_string()));
+            env.push((upper, url.to_string()));
+        }
+    }
+    env
+}
+
+/// Whether requests go through a proxy, either from settings or from the environment
+pub fn proxied(interface: &Interface) -> bool {
+    !interface.config().proxies().is_empty()
+        || ["HTTP_PROXY", "HTTPS_PROXY", "http_proxy", "https_proxy"]
+            .iter()
+            .any(|var| std::env::var_os(var).is_some())
+}
+
+pub async fn download(
+    url: &str,
+    path: &std::path::Path,
+    interface: &Arc<Interface>,
+    name: &str,
+) -> Result<(), LaunchError> {
+    match retry(
+        interface,
+        || download_attempt(url, path, interface, name),
+        DownloadError::is_transient,
+    )
+    .await
+    {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(LaunchError::Cancelled),
+        Err(err) => {
+            if let DownloadError::Timeout(_) = err {
+                // A dead connection may have left garbage, start over next time
+                std::fs::remove_file(part_path(path)).ok();
+            }
+            Err(LaunchError::download(name, err, interface))
+        }
+    }
+}
+
+pub fn part_path(path: &std::path::Path) -> std::path::PathBuf {
+    let mut part_name = path.file_name().unwrap_or_default().to_os_string();
+    part_name.push(".part");
+    path.with_file_name(part_name)
+}
+
+async fn download_attempt(
+    url: &str,
+    path: &std::path::Path,
+    interface: &Arc<Interface>,
+    name: &str,
+) -> Result<bool, DownloadError> {
+    use std::io::Write;
+    let part = part_path(path);
+    let resume_from = std::fs::metadata(&part).map_or(0, |meta| meta.len());
+
+    let mut request = client(interface).get(url);
+    if resume_from > 0 {
+        request = request.header(reqwest::header::RANGE, format!("bytes={}-", resume_from));
+    }
+
+    let read_timeout = interface.config().read_timeout_secs.max(1);
+    let mut response =
+        tokio::time::timeout(std::time::Duration::from_secs(read_timeout), request.send())
+            .await
+            .map_err(|_| DownloadError::Timeout(read_timeout))??;
+    // Redirects are already followed, this is the status of the final response
+    let status = response.status();
+    if !status.is_success() {
+        if status == reqwest::StatusCode::RANGE_NOT_SATISFIABLE {
+            // The partial file doesn't match the upload anymore, the retry starts over
+            std::fs::remove_file(&part).ok();
+        }
+        return Err(DownloadError::Status {
+            status,
+            body: error_snippet(response, read_timeout).await,
+        });
+    }
+    let resumed = status == reqwest::StatusCode::PARTIAL_CONTENT;
+    let mut file = if resumed {
+        interface.info(format!("Resuming download of {}", name));
+        std::fs::OpenOptions::new().append(true).open(&part)?
+    } else {
+        File::create(&part)?
+    };
+    let mut progress = if resumed { resume_from } else { 0 };
+    let content_length = response.content_length().map(|length| length + progress);
+    // Samples from the last second, used for a rolling average of the speed
+    let mut samples = std::collections::VecDeque::from([(std::time::Instant::now(), progress)]);
+    // Updating the bar on every chunk would mean thousands of repaints per second
+    const PROGRESS_INTERVAL: std::time::Duration = std::time::Duration::from_millis(100);
+    let mut last_update = None::<std::time::Instant>;
+    let mut speed = 0.0;
+    let limit = interface
+        .config()
+        .max_download_kbps
+        .map(|kbps| kbps.max(1) as f64 * 1024.0);
+    let (throttle_start, mut throttled) = (std::time::Instant::now(), 0u64);
+    // A stalled server would otherwise keep the progress bar up forever
+    while let Some(chunk) = tokio::time::timeout(
+        std::time::Duration::from_secs(read_timeout),
+        response.chunk(),
+    )
+    .await
+    .map_err(|_| DownloadError::Timeout(read_timeout))??
+    {
+        if interface.cancelled() {
+            return Ok(false);
+        }
+        file.write_all(&chunk)?;
+        progress += chunk.len() as u64;
+        if let Some(limit) = limit {
+            // Sleeps until the average since the start is back under the limit
+            throttled += chunk.len() as u64;
+            let due = std::time::Duration::from_secs_f64(throttled as f64 / limit);
+            tokio::time::sleep(due.saturating_sub(throttle_start.elapsed())).await;
+        }
+
+        let now = std::time::Instant::now();
+        samples.push_back((now, progress));
+        while samples.len() > 2 && now - samples[0].0 > std::time::Duration::from_secs(1) {
+            samples.pop_front();
         }
-        Err(err) => Err(err),
+        if last_update.is_some_and(|last| now - last < PROGRESS_INTERVAL) {
+            continue;
+        }
+        last_update = Some(now);
+        let (start, start_progress) = samples[0];
+        let elapsed = (now - start).as_secs_f64();
+        speed = if elapsed > 0.0 {
+            (progress - start_progress) as f64 / elapsed
+        } else {
+            0.0
+        };
+        interface.download_progress(progress, content_length, speed);
+    }
+    interface.download_progress(progress, content_length, speed);
+    drop(file);
+    std::fs::rename(&part, path)?;
+    Ok(true)
+}
+
+/// The start of a text error page, e.g. GitHub's "Not Found"
+async fn error_snippet(response: reqwest::Response, read_timeout: u64) -> String {
+    const SNIPPET_CHARS: usize = 200;
+    let text = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|kind| kind.to_str().ok())
+        .is_some_and(|kind| {
+            kind.starts_with("text/") || kind.contains("json") || kind.contains("xml")
+        });
+    if !text {
+        return String::new();
+    }
+    let body = tokio::time::timeout(
+        std::time::Duration::from_secs(read_timeout),
+        response.text(),
+    )
+    .await;
+    let body = match body {
+        Ok(Ok(body)) => body,
+        _ => return String::new(),
+    };
+    let body = body.split_whitespace().collect::<Vec<_>>().join(" ");
+    match body.char_indices().nth(SNIPPET_CHARS) {
+        Some((end, _)) => format!("{}...", &body[..end]),
+        None => body,
+    }
+}
+
+pub fn sha256_file(path: &std::path::Path) -> std::io::Result<String> {
+    use sha2::{Digest, Sha256};
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Checks the minisign signature of a download against the trusted key from settings
+pub async fn verify_signature(
+    path: &std::path::Path,
+    signature_url: Option<&str>,
+    interface: &Arc<Interface>,
+) -> Result<(), LaunchError> {
+    let Some(signature_url) = signature_url else {
+        return Err(LaunchError::Verification(
+            "The release isn't signed, turn off signature verification to run it".to_owned(),
+        ));
     };
-    bytes
+    // Either the key itself or the whole minisign.pub file
+    let key = interface.config().trusted_pubkey.clone();
+    let key = key
+        .lines()
+        .map(str::trim)
+        .rfind(|line| !line.is_empty() && !line.starts_with("untrusted comment:"))
+        .unwrap_or_default()
+        .to_owned();
+    let key = minisign_verify::PublicKey::from_base64(&key).map_err(|err| {
+        LaunchError::Verification(format!("Invalid trusted public key in settings: {}", err))
+    })?;
+    interface.info("Verifying signature");
+    let signature = retry(
+        interface,
+        || async {
+            client(interface)
+                .get(signature_url)
+                .send()
+                .await?
+                .error_for_status()?
+                .text()
+                .await
+        },
+        |err: &reqwest::Error| err.is_connect() || err.is_timeout(),
+    )
+    .await
+    .map_err(|err| LaunchError::download("the signature", err.into(), interface))?;
+    minisign_verify::Signature::decode(&signature)
+        .map_err(|err| err.to_string())
+        .and_then(|signature| {
+            let data = std::fs::read(path).map_err(|err| err.to_string())?;
+            key.verify(&data, &signature, false)
+                .map_err(|err| err.to_string())
+        })
         .map_err(|err| {
-            interface.error(format!("Failed to download {}: {}", name, err));
+            LaunchError::Verification(format!(
+                "Signature verification failed, not running it: {}",
+                err
+            ))
         })
-        .ok()
 }
 
-pub fn unpack(bytes: &[u8], path: &std::path::Path, interface: &Arc<Interface>) -> bool {
-    if let Err(err) = zip_extract::extract(std::io::Cursor::new(bytes), path, true) {
-        interface.error(format!("Failed to unpack version sources: {}", err));
-        false
+pub async fn verify_checksum(
+    path: &std::path::Path,
+    asset_url: &str,
+    checksum_url: &str,
+    interface: &Arc<Interface>,
+) -> Result<(), LaunchError> {
+    interface.info("Verifying checksum");
+    let checksums = retry(
+        interface,
+        || async {
+            client(interface)
+                .get(checksum_url)
+                .send()
+                .await?
+                .error_for_status()?
+                .text()
+                .await
+        },
+        |err: &reqwest::Error| err.is_connect() || err.is_timeout(),
+    )
+    .await
+    .map_err(|err| LaunchError::download("checksums", err.into(), interface))?;
+
+    // Both "<hash>" and "<hash>  <file>" per line are common
+    let asset_name = asset_url.rsplit('/').next().unwrap_or_default();
+    let expected = checksums.lines().find_map(|line| {
+        let mut parts = line.split_whitespace();
+        let hash = parts.next()?;
+        match parts.next() {
+            Some(file) if file.trim_start_matches('*') != asset_name => None,
+            _ => Some(hash.to_lowercase()),
+        }
+    });
+    let Some(expected) = expected else {
+        return Err(LaunchError::Verification(format!(
+            "No checksum found for {}",
+            asset_name
+        )));
+    };
+
+    let actual = sha256_file(path).map_err(|err| {
+        LaunchError::Verification(format!("Failed to read downloaded file: {}", err))
+    })?;
+    if actual != expected {
+        std::fs::remove_file(path).ok();
+        return Err(LaunchError::ChecksumMismatch {
+            file: asset_name.to_owned(),
+            expected,
+            actual,
+        });
+    }
+    Ok(())
+}
+
+pub fn github(interface: &Interface) -> Arc<octocrab::Octocrab> {
+    let token = interface.config().github_token.clone();
+    match token.map(|token| octocrab::Octocrab::builder().personal_token(token).build()) {
+        Some(Ok(github)) => Arc::new(github),
+        _ => octocrab::instance(),
+    }
+}
+
+/// Drops a rejected token, so the next request goes unauthenticated
+pub fn check_unauthorized(err: &octocrab::Error, interface: &Interface) {
+    let octocrab::Error::GitHub { source, .. } = err else {
+        return;
+    };
+    if source.message.contains("Bad credentials") {
+        let mut config = interface.config();
+        if config.github_token.take().is_some() {
+            config.save(interface);
+            drop(config);
+            interface.warning(
+                "GitHub token was rejected and has been removed, please set a new one in settings",
+            );
+        }
+    }
+}
+
+pub async fn retry<T, E, F: Future<Output = Result<T, E>>>(
+    interface: &Arc<Interface>,
+    mut action: impl FnMut() -> F,
+    transient: impl Fn(&E) -> bool,
+) -> Result<T, E> {
+    let retries = interface.config().retries;
+    let mut delay = std::time::Duration::from_millis(500);
+    let mut attempt = 0;
+    loop {
+        match action().await {
+            Err(err) if attempt < retries && transient(&err) && !interface.cancelled() => {
+                attempt += 1;
+                interface.info(format!("Retrying ({}/{})...", attempt, retries));
+                tokio::time::sleep(delay).await;
+                delay *= 2;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// What to do with the top-level directory of an archive
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Toplevel {
+    Keep,
+    /// The archive must have a single root directory, like GitHub zipballs do
+    Strip,
+    StripIfShared,
+}
+
+impl Toplevel {
+    /// The directory to strip from the entry names, if any
+    fn prefix(self, names: &[std::path::PathBuf]) -> Result<Option<std::path::PathBuf>, String> {
+        match self {
+            Self::Keep => Ok(None),
+            Self::Strip => shared_root(names).map(Some).ok_or_else(|| {
+                "expected a single top-level directory, the archive layout is not supported"
+                    .to_owned()
+            }),
+            Self::StripIfShared => Ok(shared_root(names)),
+        }
+    }
+}
+
+/// Archives are only kept with `keep_archives`, anything else would be downloaded again anyway
+pub fn remove_archive(archive: &std::path::Path, interface: &Interface) {
+    if !interface.config().keep_archives {
+        std::fs::remove_file(archive).ok();
+    }
+}
+
+pub fn dir_size(path: &std::path::Path) -> u64 {
+    std::fs::read_dir(path).map_or(0, |dir| {
+        dir.flatten()
+            .map(|entry| match entry.file_type() {
+                Ok(kind) if kind.is_dir() => dir_size(&entry.path()),
+                _ => entry.metadata().map_or(0, |metadata| metadata.len()),
+            })
+            .sum()
+    })
+}
+
+pub fn clear_download_cache(interface: &Interface) {
+    let mut freed = 0;
+    if let Ok(dir) = std::fs::read_dir(get_versions_path()) {
+        for version in dir.flatten() {
+            let cache = version.path().join("cache");
+            let size = dir_size(&cache);
+            if std::fs::remove_dir_all(&cache).is_ok() {
+                freed += size;
+            }
+        }
+    }
+    interface.info(format!(
+        "Cleared the download cache, freed {}",
+        format_size(freed)
+    ));
+}
+
+pub async fn unpack(
+    archive: &std::path::Path,
+    path: &std::path::Path,
+    toplevel: Toplevel,
+    interface: &Arc<Interface>,
+) -> Result<(), LaunchError> {
+    // Listing the entries of a compressed tarball means decompressing it once already
+    interface.set_progress(0.0, "Reading the archive");
+    let result = {
+        let (archive, path, interface) = (archive.to_owned(), path.to_owned(), interface.clone());
+        tokio::task::spawn_blocking(move || match ArchiveFormat::detect(&archive)? {
+            ArchiveFormat::TarGz => extract_tar(
+                || Ok(flate2::read::GzDecoder::new(File::open(&archive)?)),
+                &path,
+                toplevel,
+                &interface,
+            ),
+            ArchiveFormat::TarXz => extract_tar(
+                || Ok(xz2::read::XzDecoder::new(File::open(&archive)?)),
+                &path,
+                toplevel,
+                &interface,
+            ),
+            ArchiveFormat::Zip => extract_zip(&archive, &path, toplevel, &interface),
+            ArchiveFormat::SevenZip => extract_7z(&archive, &path, toplevel, &interface),
+        })
+        .await
+        .unwrap_or_else(|err| Err(err.to_string()))
+    };
+    result.map_err(|err| {
+        if interface.cancelled() {
+            LaunchError::Cancelled
+        } else {
+            LaunchError::Unpack(err)
+        }
+    })
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ArchiveFormat {
+    Zip,
+    TarGz,
+    TarXz,
+    SevenZip,
+}
+
+impl ArchiveFormat {
+    /// By the magic bytes, the extension is only trusted for files too short to have them
+    fn detect(archive: &std::path::Path) -> Result<Self, String> {
+        let magic = read_magic(archive).map_err(|err| err.to_string())?;
+        if let Some(format) = Self::sniff(&magic) {
+            return format;
+        }
+        let name = archive.to_string_lossy().to_lowercase();
+        match archive_extension(&name) {
+            _ if magic.len() >= 4 => Err(format!(
+                "unrecognized archive format (starts with {:02x?})",
+                magic
+            )),
+            "tar.gz" => Ok(Self::TarGz),
+            "tar.xz" => Ok(Self::TarXz),
+            "7z" => Ok(Self::SevenZip),
+            _ => Ok(Self::Zip),
+        }
+    }
+
+    /// None when the bytes aren't the start of any archive, an error for unsupported ones
+    fn sniff(magic: &[u8]) -> Option<Result<Self, String>> {
+        Some(
+            if magic.starts_with(b"PK\x03\x04") || magic.starts_with(b"PK\x05\x06") {
+                Ok(Self::Zip)
+            } else if magic.starts_with(&[0x1f, 0x8b]) {
+                Ok(Self::TarGz)
+            } else if magic.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0x00]) {
+                Ok(Self::TarXz)
+            } else if magic.starts_with(&[b'7', b'z', 0xbc, 0xaf, 0x27, 0x1c]) {
+                Ok(Self::SevenZip)
+            } else if magic.starts_with(b"Rar!") {
+                Err("RAR archives are not supported".to_owned())
+            } else if magic.starts_with(b"BZh") {
+                Err("bzip2 archives are not supported".to_owned())
+            } else {
+                return None;
+            },
+        )
+    }
+}
+
+fn read_magic(path: &std::path::Path) -> std::io::Result<Vec<u8>> {
+    use std::io::Read;
+    let mut magic = Vec::new();
+    File::open(path)?.take(6).read_to_end(&mut magic)?;
+    Ok(magic)
+}
+
+/// Whether the name of a download says it's an archive or a disk image
+pub fn packaged_url(url: &str) -> bool {
+    let mut url = url.to_lowercase();
+    url.truncate(url.find(['?', '#']).unwrap_or(url.len()));
+    url.ends_with(".dmg") || url.ends_with(".zip") || archive_extension(&url) != "zip"
+}
+
+/// Whether a download is an archive or a disk image rather than a bare executable
+pub fn is_packaged(path: &std::path::Path, url: &str) -> std::io::Result<bool> {
+    Ok(packaged_url(url) || ArchiveFormat::sniff(&read_magic(path)?).is_some())
+}
+
+/// File extension to save a downloaded archive with
+pub fn archive_extension(url: &str) -> &'static str {
+    let url = url.to_lowercase();
+    if url.ends_with(".tar.gz") || url.ends_with(".tgz") {
+        "tar.gz"
+    } else if url.ends_with(".tar.xz") {
+        "tar.xz"
+    } else if url.ends_with(".7z") {
+        "7z"
     } else {
-        true
+        "zip"
     }
 }
 
+/// Same as zip's enclosed_name, entries must not escape the target directory
+fn enclosed(name: &std::path::Path) -> bool {
+    name.components().all(|component| {
+        matches!(
+            component,
+            std::path::Component::Normal(_) | std::path::Component::CurDir
+        )
+    })
+}
+
+/// Whether `target` ends up in `root` after resolving the symlinks among its existing parents
+fn stays_inside(root: &std::path::Path, target: &std::path::Path) -> bool {
+    target
+        .ancestors()
+        .skip(1)
+        .find(|ancestor| ancestor.exists())
+        .and_then(|ancestor| ancestor.canonicalize().ok())
+        .is_some_and(|ancestor| ancestor.starts_with(root))
+}
+
+/// The directory all entries are in, if there is exactly one
+fn shared_root(names: &[std::path::PathBuf]) -> Option<std::path::PathBuf> {
+    let root = names.first()?.components().next()?;
+    let shared = names
+        .iter()
+        .all(|name| name.components().next() == Some(root));
+    let nested = names.iter().any(|name| name.components().count() > 1);
+    (shared && nested).then(|| std::path::PathBuf::from(root.as_os_str()))
+}
+
+/// Tar archives can't be listed without reading them, so `open` is called once to find
+/// the top-level directory and once more to extract
+fn extract_tar<R: std::io::Read>(
+    open: impl Fn() -> std::io::Result<R>,
+    path: &std::path::Path,
+    toplevel: Toplevel,
+    interface: &Interface,
+) -> Result<(), String> {
+    let mut archive = tar::Archive::new(open().map_err(|err| err.to_string())?);
+    let names = archive
+        .entries()
+        .map_err(|err| err.to_string())?
+        .filter_map(|entry| entry.ok()?.path().ok().map(|name| name.into_owned()))
+        .filter(|name| enclosed(name))
+        .collect::<Vec<_>>();
+    let toplevel = toplevel.prefix(&names)?;
+
+    let count = names.len().max(1);
+    std::fs::create_dir_all(path).map_err(|err| err.to_string())?;
+    let root = path.canonicalize().map_err(|err| err.to_string())?;
+    let mut archive = tar::Archive::new(open().map_err(|err| err.to_string())?);
+    for (index, entry) in archive
+        .entries()
+        .map_err(|err| err.to_string())?
+        .enumerate()
+    {
+        if interface.cancelled() {
+            return Err("cancelled".to_owned());
+        }
+        let mut entry = entry.map_err(|err| err.to_string())?;
+        let name = entry.path().map_err(|err| err.to_string())?.into_owned();
+        // Hard links point at paths relative to the launcher, not to the archive
+        if !enclosed(&name) || entry.header().entry_type().is_hard_link() {
+            continue;
+        }
+        let name = match &toplevel {
+            Some(toplevel) => name.strip_prefix(toplevel).unwrap_or(&name).to_owned(),
+            None => name,
+        };
+        if name.as_os_str().is_empty() {
+            continue;
+        }
+        interface.replace_progress((index + 1) as f32 / count as f32);
+        let target = path.join(&name);
+        // Entry::unpack writes wherever it's told, an earlier symlink entry could point a
+        // directory of the archive outside the version folder
+        if !stays_inside(&root, &target) {
+            return Err(format!("{:?} points outside the version folder", name));
+        }
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent).map_err(|err| err.to_string())?;
+        }
+        // Keeps the permissions from the archive, including the executable bit
+        entry.unpack(&target).map_err(|err| err.to_string())?;
+    }
+    Ok(())
+}
+
+/// Extracts entry by entry, reporting progress. Runs on a blocking thread
+fn extract_zip(
+    archive: &std::path::Path,
+    path: &std::path::Path,
+    toplevel: Toplevel,
+    interface: &Interface,
+) -> Result<(), String> {
+    let file = File::open(archive).map_err(|err| err.to_string())?;
+    let mut zip =
+        zip::ZipArchive::new(std::io::BufReader::new(file)).map_err(|err| err.to_string())?;
+
+    let names = (0..zip.len())
+        .filter_map(|index| {
+            zip.by_index(index)
+                .ok()?
+                .enclosed_name()
+                .map(|name| name.to_owned())
+        })
+        .collect::<Vec<_>>();
+    let toplevel = toplevel.prefix(&names)?;
+
+    // Label with the current file only for archives big enough to take a while
+    let count = zip.len();
+    let detailed = count > 100;
+    for index in 0..count {
+        if interface.cancelled() {
+            return Err("cancelled".to_owned());
+        }
+        let mut entry = zip.by_index(index).map_err(|err| err.to_string())?;
+        let Some(name) = entry.enclosed_name().map(|name| name.to_owned()) else {
+            continue;
+        };
+        let name = match &toplevel {
+            Some(toplevel) => name.strip_prefix(toplevel).unwrap_or(&name).to_owned(),
+            None => name,
+        };
+        if name.as_os_str().is_empty() {
+            continue;
+        }
+
+        let progress = (index + 1) as f32 / count as f32;
+        if detailed {
+            interface.set_progress(progress, name.to_string_lossy());
+        } else {
+            interface.replace_progress(progress);
+        }
+
+        let target = path.join(&name);
+        if entry.is_dir() {
+            std::fs::create_dir_all(&target).map_err(|err| err.to_string())?;
+            continue;
+        }
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent).map_err(|err| err.to_string())?;
+        }
+        let mut output = File::create(&target).map_err(|err| err.to_string())?;
+        std::io::copy(&mut entry, &mut output).map_err(|err| err.to_string())?;
+        #[cfg(unix)]
+        if let Some(mode) = entry.unix_mode() {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&target, std::fs::Permissions::from_mode(mode)).ok();
+        }
+    }
+    Ok(())
+}
+
+fn extract_7z(
+    archive: &std::path::Path,
+    path: &std::path::Path,
+    toplevel: Toplevel,
+    interface: &Interface,
+) -> Result<(), String> {
+    let mut reader = sevenz_rust::SevenZReader::open(archive, sevenz_rust::Password::empty())
+        .map_err(|err| err.to_string())?;
+    let names = reader
+        .archive()
+        .files
+        .iter()
+        .map(|entry| std::path::PathBuf::from(entry.name()))
+        .filter(|name| enclosed(name))
+        .collect::<Vec<_>>();
+    let toplevel = toplevel.prefix(&names)?;
+
+    let count = names.len().max(1);
+    let mut index = 0;
+    let mut cancelled = false;
+    reader
+        .for_each_entries(|entry, data| {
+            if interface.cancelled() {
+                cancelled = true;
+                return Ok(false);
+            }
+            index += 1;
+            let name = std::path::PathBuf::from(entry.name());
+            if !enclosed(&name) {
+                return Ok(true);
+            }
+            let name = match &toplevel {
+                Some(toplevel) => name.strip_prefix(toplevel).unwrap_or(&name).to_owned(),
+                None => name,
+            };
+            if name.as_os_str().is_empty() {
+                return Ok(true);
+            }
+            interface.replace_progress(index as f32 / count as f32);
+            let target = path.join(&name);
+            if entry.is_directory() {
+                std::fs::create_dir_all(&target)?;
+                return Ok(true);
+            }
+            if let Some(parent) = target.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::io::copy(data, &mut File::create(&target)?)?;
+            // p7zip stores unix permissions in the high bits of the attributes
+            #[cfg(unix)]
+            if entry.has_windows_attributes && entry.windows_attributes & 0x8000 != 0 {
+                use std::os::unix::fs::PermissionsExt;
+                let mode = entry.windows_attributes >> 16;
+                std::fs::set_permissions(&target, std::fs::Permissions::from_mode(mode)).ok();
+            }
+            Ok(true)
+        })
+        .map_err(|err| err.to_string())?;
+    if cancelled {
+        return Err("cancelled".to_owned());
+    }
+    Ok(())
+}
+
 pub async fn run_command(
+    command: &str,
+    args: &[&str],
+    path: Option<&std::path::Path>,
+    interface: &Arc<Interface>,
+    line_callback: impl FnMut(&str),
+) -> Result<(), LaunchError> {
+    run_command_with(command, args, path, interface, line_callback, |_| false).await
+}
+
+/// Like `run_command`, but stderr lines go to `stderr_callback` first, which returns true
+/// for lines it handled and that shouldn't be logged
+pub async fn run_command_with(
     command: &str,
     args: &[&str],
     path: Option<&std::path::Path>,
     interface: &Arc<Interface>,
     mut line_callback: impl FnMut(&str),
-) -> bool {
+    mut stderr_callback: impl FnMut(&str) -> bool,
+) -> Result<(), LaunchError> {
     use std::process::Stdio;
     use tokio::process::Command;
-    use tokio_process_stream::ProcessLineStream;
+    use tokio_process_stream::ProcessChunkStream;
     use tokio_stream::StreamExt;
+    let command_line = std::iter::once(command)
+        .chain(args.iter().copied())
+        .collect::<Vec<_>>()
+        .join(" ");
     let mut command = Command::new(command);
     command
         .args(args)
         .stdout(Stdio::inherit())
-        .stderr(Stdio::inherit());
+        .stderr(Stdio::inherit())
+        .envs(proxy_env(interface))
+        .kill_on_drop(true);
     if let Some(path) = path {
         command.current_dir(path);
     }
-    let mut procstream = match ProcessLineStream::try_from(command) {
-        Ok(procstream) => procstream,
-        Err(err) => {
-            interface.error(format!("Failed to run command: {}", err));
-            return false;
+    // Chunks instead of lines, progress output (e.g. git) rewrites its line with '\r'
+    let mut procstream =
+        ProcessChunkStream::try_from(command).map_err(|error| LaunchError::Command {
+            command: command_line.clone(),
+            error,
+        })?;
+    const TAIL_LINES: usize = 30;
+    let mut tail = std::collections::VecDeque::with_capacity(TAIL_LINES);
+    let mut remember = |line: &str| {
+        if tail.len() == TAIL_LINES {
+            tail.pop_front();
         }
+        tail.push_back(line.to_owned());
     };
-    while let Some(item) = procstream.next().await {
-        use tokio_process_stream::Item;
-        match item {
-            Item::Stdout(line) => line_callback(&line),
-            Item::Stderr(err) => {
-                if !err.contains("Cloning into") {
-                    interface.log().push(RichText::new(err).color(Color32::RED));
+    let mut stdout = LineSplitter::default();
+    let mut stderr = LineSplitter::default();
+    let watchdog = interface.config().watchdog_minutes;
+    let mut last_output = std::time::Instant::now();
+    let mut warned = false;
+    loop {
+        if interface.cancelled() {
+            return Err(LaunchError::Cancelled);
+        }
+        let item = match tokio::time::timeout(
+            std::time::Duration::from_millis(200),
+            procstream.next(),
+        )
+        .await
+        {
+            Ok(Some(item)) => item,
+            Ok(None) => break,
+            Err(_) => {
+                if watchdog > 0 && !warned && last_output.elapsed().as_secs() >= watchdog * 60 {
+                    warned = true;
+                    interface.warning(format!(
+                            "No output for {} minutes, the command might be stuck. Press cancel to abort it",
+                            watchdog
+                        ));
                 }
+                continue;
+            }
+        };
+        last_output = std::time::Instant::now();
+        warned = false;
+        use tokio_process_stream::Item;
+        let (stdout_lines, stderr_lines) = match &item {
+            Item::Stdout(chunk) => (stdout.push(chunk), Vec::new()),
+            Item::Stderr(chunk) => (Vec::new(), stderr.push(chunk)),
+            Item::Done(_) => (stdout.finish(), stderr.finish()),
+        };
+        for line in stdout_lines {
+            remember(&line);
+            line_callback(&line);
+        }
+        for err in stderr_lines {
+            if stderr_callback(&err) {
+                continue;
+            }
+            remember(&err);
+            if !err.contains("Cloning into") {
+                interface.push_log(&err, LogLevel::Error);
             }
-            Item::Done(status) => match status {
+        }
+        if let Item::Done(status) = item {
+            match status {
                 Ok(status) => {
                     if !status.success() {
-                        interface.error("Failed to run command!");
-                        return false;
+                        report_tail(&tail, interface);
+                        return Err(LaunchError::CommandFailed {
+                            command: command_line,
+                            code: status.code(),
+                        });
                     }
                 }
-                Err(err) => {
-                    interface.error(format!("Failed to run command: {}", err));
-                    return false;
+                Err(error) => {
+                    return Err(LaunchError::Command {
+                        command: command_line,
+                        error,
+                    });
                 }
-            },
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Splits output chunks into lines on both '\n' and '\r'
+#[derive(Default)]
+struct LineSplitter {
+    buffer: Vec<u8>,
+}
+
+impl LineSplitter {
+    fn push(&mut self, chunk: &[u8]) -> Vec<String> {
+        let mut lines = Vec::new();
+        for &byte in chunk {
+            if byte == b'\n' || byte == b'\r' {
+                lines.extend(self.finish());
+            } else {
+                self.buffer.push(byte);
+            }
         }
+        lines
     }
+
+    fn finish(&mut self) -> Vec<String> {
+        if self.buffer.is_empty() {
+            return Vec::new();
+        }
+        let line = String::from_utf8_lossy(&self.buffer).into_owned();
+        self.buffer.clear();
+        vec![line]
+    }
+}
+
+/// Make prints "[ 42%] Building ...", Ninja "[42/100] Building ..."
+pub fn build_progress(line: &str) -> Option<(f32, String)> {
+    let (progress, _) = line.strip_prefix('[')?.split_once(']')?;
+    let progress = progress.trim();
+    if let Some(percentage) = progress.strip_suffix('%') {
+        let percentage = percentage.trim().parse::<u32>().ok()?.min(100);
+        return Some((percentage as f32 / 100.0, format!("{}%", percentage)));
+    }
+    let (done, total) = progress.split_once('/')?;
+    let (done, total) = (
+        done.trim().parse::<u32>().ok()?,
+        total.trim().parse::<u32>().ok()?,
+    );
+    let done = done.min(total);
+    Some((
+        done as f32 / total.max(1) as f32,
+        format!("{}/{} ({}%)", done, total, done * 100 / total.max(1)),
+    ))
+}
+
+/// Shows git `--progress` lines like "Receiving objects:  42% (420/1000)" on the progress bar
+pub fn git_progress(line: &str, interface: &Interface) -> bool {
+    let line = line.trim_start_matches("remote:").trim();
+    let Some((phase, rest)) = line.split_once(':') else {
+        return false;
+    };
+    let label = match phase {
+        "Enumerating objects" => "Enumerating objects",
+        "Counting objects" => "Counting objects",
+        "Compressing objects" => "Compressing objects",
+        "Receiving objects" => "Receiving objects",
+        "Unpacking objects" => "Unpacking objects",
+        "Resolving deltas" => "Resolving deltas",
+        "Updating files" => "Checking out files",
+        _ => return false,
+    };
+    let Some(percentage) = rest
+        .trim()
+        .split_once('%')
+        .and_then(|(percentage, _)| percentage.trim().parse::<u32>().ok())
+    else {
+        // e.g. "Enumerating objects: 5, done."
+        return true;
+    };
+    interface.set_progress(
+        percentage.min(100) as f32 / 100.0,
+        format!("{}: {}%", label, percentage),
+    );
     true
 }
 
+/// Repeats the last output lines of a failed command, so the actual error is easy to find
+fn report_tail(tail: &std::collections::VecDeque<String>, interface: &Interface) {
+    if tail.is_empty() {
+        return;
+    }
+    interface.push_log("Last output of the failed command:", LogLevel::Warning);
+    for line in tail {
+        let lower = line.to_lowercase();
+        let level = if lower.contains("error") || lower.contains("fatal:") {
+            LogLevel::Error
+        } else {
+            LogLevel::Output
+        };
+        interface.push_log(line, level);
+    }
+}
+
+/// Waits a moment for an AppImage that exits right away because it can't be mounted with FUSE.
+/// The game is tracked meanwhile, `mentioned_fuse` checks its output
+pub async fn appimage_fuse_failed(
+    interface: &Interface,
+    mentioned_fuse: impl Fn() -> bool,
+) -> bool {
+    const GRACE: std::time::Duration = std::time::Duration::from_secs(2);
+    let start = std::time::Instant::now();
+    loop {
+        // The UI drops the child once it exited, so a missing one has exited too
+        let exited = match interface.game().as_mut() {
+            Some(child) => match child.try_wait() {
+                Ok(Some(status)) if status.success() => return false,
+                Ok(Some(_)) => true,
+                Ok(None) => false,
+                Err(_) => return false,
+            },
+            None => true,
+        };
+        if exited {
+            break;
+        }
+        if start.elapsed() >= GRACE {
+            return false;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    }
+    // The output is forwarded asynchronously
+    tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+    mentioned_fuse()
+}
+
+/// Passes a piped stderr of the game on to the launcher's, for when the output isn't captured.
+/// The flag is set once it mentions FUSE
+pub fn pass_through_stderr(
+    child: &mut std::process::Child,
+) -> Option<Arc<std::sync::atomic::AtomicBool>> {
+    use std::io::{BufRead, Write};
+    let stderr = child.stderr.take()?;
+    let mentioned = Arc::new(std::sync::atomic::AtomicBool::new(false));
+    let flag = mentioned.clone();
+    // Lives as long as the game, like the capture tasks
+    std::thread::spawn(move || {
+        for line in std::io::BufReader::new(stderr)
+            .split(b'\n')
+            .map_while(Result::ok)
+        {
+            let line = String::from_utf8_lossy(&line);
+            if line.to_lowercase().contains("fuse") {
+                flag.store(true, std::sync::atomic::Ordering::Relaxed);
+            }
+            writeln!(std::io::stderr(), "{}", line.trim_end_matches('\r')).ok();
+        }
+    });
+    Some(mentioned)
+}
+
+pub fn capture_output(child: &mut std::process::Child, interface: &Arc<Interface>) {
+    use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+    fn forward(
+        reader: impl AsyncRead + Unpin + Send + 'static,
+        interface: Arc<Interface>,
+        level: LogLevel,
+    ) {
+        tokio::spawn(async move {
+            let mut lines = BufReader::new(reader).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                interface.push_log(&line, level);
+            }
+        });
+    }
+
+    // Not tracked, they live as long as the game and closing the launcher shouldn't wait for it
+    if let Some(stdout) = child.stdout.take() {
+        let runtime = interface.runtime().clone();
+        let interface = interface.clone();
+        runtime.spawn(async move {
+            match tokio::process::ChildStdout::from_std(stdout) {
+                Ok(stdout) => forward(stdout, interface, LogLevel::Output),
+                Err(err) => interface.warning(format!("Failed to capture game output: {}", err)),
+            }
+        });
+    }
+    if let Some(stderr) = child.stderr.take() {
+        let runtime = interface.runtime().clone();
+        let interface = interface.clone();
+        runtime.spawn(async move {
+            match tokio::process::ChildStderr::from_std(stderr) {
+                Ok(stderr) => forward(stderr, interface, LogLevel::Error),
+                Err(err) => interface.warning(format!("Failed to capture game output: {}", err)),
+            }
+        });
+    }
+}
+
+pub async fn unpack_dmg(
+    image: &std::path::Path,
+    path: &std::path::Path,
+    interface: &Arc<Interface>,
+) -> Result<(), LaunchError> {
+    let mountpoint = path.join("mnt");
+    run_command(
+        "hdiutil",
+        &[
+            "attach",
+            "-nobrowse",
+            "-mountpoint",
+            mountpoint.to_string_lossy().as_ref(),
+            image.to_string_lossy().as_ref(),
+        ],
+        None,
+        interface,
+        |_| (),
+    )
+    .await?;
+
+    let bundles = std::fs::read_dir(&mountpoint)
+        .map(|dir| {
+            dir.flatten()
+                .map(|entry| entry.path())
+                .filter(|entry| entry.extension().is_some_and(|ext| ext == "app"))
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    let mut result = if bundles.is_empty() {
+        Err(LaunchError::Unpack(
+            "No application bundle found in the disk image".to_owned(),
+        ))
+    } else {
+        Ok(())
+    };
+    for bundle in bundles {
+        let copied = run_command(
+            "cp",
+            &[
+                "-R",
+                bundle.to_string_lossy().as_ref(),
+                path.to_string_lossy().as_ref(),
+            ],
+            None,
+            interface,
+            |_| (),
+        )
+        .await;
+        result = result.and(copied);
+    }
+
+    if let Err(err) = run_command(
+        "hdiutil",
+        &["detach", mountpoint.to_string_lossy().as_ref()],
+        None,
+        interface,
+        |_| (),
+    )
+    .await
+    {
+        interface.warning(format!("Failed to detach the disk image: {}", err));
+    }
+    result
+}
+
+/// Looks for the game executable anywhere in a version folder or an unpacked archive, e.g. for
+/// when the recorded path is stale
+pub fn find_executable(path: &std::path::Path) -> Option<std::path::PathBuf> {
+    let downloaded = std::path::PathBuf::from(downloaded_name());
+    if is_executable(&path.join(&downloaded)) {
+        return Some(downloaded);
+    }
+    if cfg!(target_os = "macos") {
+        if let Some(binary) = find_app_binary(path) {
+            return Some(binary);
+        }
+    }
+    let names = [
+        binary_name(),
+        downloaded.file_name()?.to_string_lossy().into_owned(),
+    ];
+    // Breadth first, so build/VoxelEngine wins over something deep in the build tree
+    let mut dirs = std::collections::VecDeque::from([path.to_path_buf()]);
+    while let Some(dir) = dirs.pop_front() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let file = entry.path();
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if file.is_dir() {
+                if !matches!(
+                    name.as_str(),
+                    ".git" | "src" | "CMakeFiles" | "res" | "cache" | "profiles"
+                ) {
+                    dirs.push_back(file);
+                }
+            } else if names.contains(&name) && is_executable(&file) {
+                return file.strip_prefix(path).ok().map(|file| file.to_path_buf());
+            }
+        }
+    }
+    None
+}
+
+/// The executable of the first app bundle in `path` that has one
+pub fn find_app_binary(path: &std::path::Path) -> Option<std::path::PathBuf> {
+    let Ok(entries) = std::fs::read_dir(path) else {
+        return None;
+    };
+    for entry in entries.flatten() {
+        let bundle = entry.path();
+        if bundle.extension().is_none_or(|ext| ext != "app") {
+            continue;
+        }
+        let contents = bundle.join("Contents");
+        // Contents/MacOS may also hold helpers, the bundle names its main executable
+        let name = std::fs::read_to_string(contents.join("Info.plist"))
+            .ok()
+            .and_then(|plist| bundle_executable(&plist))
+            .unwrap_or_else(binary_name);
+        let binary = contents.join("MacOS").join(name);
+        if binary.is_file() {
+            return binary
+                .strip_prefix(path)
+                .ok()
+                .map(|path| path.to_path_buf());
+        }
+    }
+    None
+}
+
+/// `CFBundleExecutable` of an XML Info.plist
+fn bundle_executable(plist: &str) -> Option<String> {
+    let (_, rest) = plist.split_once("<key>CFBundleExecutable</key>")?;
+    let (_, rest) = rest.split_once("<string>")?;
+    let (name, _) = rest.split_once("</string>")?;
+    let name = name.trim();
+    (!name.is_empty() && enclosed(std::path::Path::new(name))).then(|| name.to_owned())
+}
+
+const ARM_TAGS: &[&str] = &["aarch64", "arm64"];
+const X86_TAGS: &[&str] = &["x86_64", "amd64", "x64", "win64"];
+
+/// Splits a command line on whitespace, keeping "quoted" or 'quoted' parts together
+pub fn split_args(line: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut arg = String::new();
+    let mut in_arg = false;
+    let mut quote = None;
+    for c in line.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => arg.push(c),
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                in_arg = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_arg {
+                    args.push(std::mem::take(&mut arg));
+                    in_arg = false;
+                }
+            }
+            (None, c) => {
+                arg.push(c);
+                in_arg = true;
+            }
+        }
+    }
+    if in_arg {
+        args.push(arg);
+    }
+    args
+}
+
+pub fn find_in_path(tool: &str) -> Option<std::path::PathBuf> {
+    let extensions = if cfg!(windows) {
+        std::env::var("PATHEXT")
+            .unwrap_or_else(|_| ".EXE;.CMD;.BAT".to_owned())
+            .split(';')
+            .map(str::to_owned)
+            .collect()
+    } else {
+        vec![String::new()]
+    };
+    std::env::split_paths(&std::env::var_os("PATH")?).find_map(|dir| {
+        extensions.iter().find_map(|ext| {
+            let path = dir.join(format!("{}{}", tool, ext));
+            path.is_file().then_some(path)
+        })
+    })
+}
+
+/// The first compiler cache found in PATH
+pub fn compiler_cache() -> Option<&'static str> {
+    ["ccache", "sccache"]
+        .into_iter()
+        .find(|tool| find_in_path(tool).is_some())
+}
+
+pub fn install_hint(tool: &str) -> String {
+    if cfg!(windows) {
+        match tool {
+            "git" => "Download it from https://git-scm.com/download/win".to_owned(),
+            "cmake" => "Download it from https://cmake.org/download/".to_owned(),
+            _ => format!(
+                "Install it with MSYS2 (https://www.msys2.org/): pacman -S {}",
+                tool
+            ),
+        }
+    } else if cfg!(target_os = "macos") {
+        format!(
+            "Install it with Homebrew (https://brew.sh/): brew install {}",
+            tool
+        )
+    } else {
+        format!(
+            "Install it with your package manager, e.g. sudo apt install {}",
+            tool
+        )
+    }
+}
+
+pub fn check_tools(tools: &[&str]) -> Result<(), LaunchError> {
+    let missing = tools
+        .iter()
+        .filter(|tool| find_in_path(tool).is_none())
+        .map(|tool| tool.to_string())
+        .collect::<Vec<_>>();
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(LaunchError::MissingTools(missing))
+    }
+}
+
+/// First line of `--version` for each build tool, for diagnostics
+pub async fn tool_versions(interface: &Arc<Interface>) -> String {
+    let mut text = String::new();
+    for tool in ["git", "cmake"] {
+        let mut first_line = None;
+        let found = find_in_path(tool).is_some()
+            && run_command(tool, &["--version"], None, interface, |line| {
+                first_line.get_or_insert_with(|| line.to_owned());
+            })
+            .await
+            .is_ok();
+        let version = match first_line {
+            Some(line) if found => line,
+            _ => "not found".to_owned(),
+        };
+        text += &format!("{}: {}\n", tool, version);
+    }
+    text
+}
+
+pub fn find_platform_asset<'a>(
+    assets: &'a [octocrab::models::repos::Asset],
+    interface: &Arc<Interface>,
+) -> Option<&'a octocrab::models::repos::Asset> {
+    let (asset, arch_specific) = select_arch_asset(assets, std::env::consts::ARCH)?;
+    if !arch_specific {
+        warn_arch_fallback(&asset.name, interface);
+    }
+    Some(asset)
+}
+
+/// For when a download that isn't built for this architecture is about to be used
+pub fn warn_arch_fallback(name: &str, interface: &Interface) {
+    if std::env::consts::ARCH != "x86_64" {
+        interface.warning(format!(
+            "No {} build of {} found, falling back to it anyway",
+            std::env::consts::ARCH,
+            name
+        ));
+    }
+}
+
+/// Returns the best asset for the platform and whether it was built for `arch` specifically
+pub fn select_arch_asset<'a>(
+    assets: &'a [octocrab::models::repos::Asset],
+    arch: &str,
+) -> Option<(&'a octocrab::models::repos::Asset, bool)> {
+    let (assets, arch_specific) = select_arch_assets(assets, arch);
+    assets.first().map(|asset| (*asset, arch_specific))
+}
+
+/// All assets for the platform, a release may ship e.g. both an installer and a portable zip.
+/// True when they were built for `arch` specifically
+pub fn select_arch_assets<'a>(
+    assets: &'a [octocrab::models::repos::Asset],
+    arch: &str,
+) -> (Vec<&'a octocrab::models::repos::Asset>, bool) {
+    let (host_tags, other_tags) = match arch {
+        "aarch64" | "arm" => (ARM_TAGS, X86_TAGS),
+        _ => (X86_TAGS, ARM_TAGS),
+    };
+    let platform = || assets.iter().filter(|asset| find_platform_version(asset));
+    let has_tag = |asset: &octocrab::models::repos::Asset, tags: &[&str]| {
+        let name = asset.name.to_lowercase();
+        tags.iter().any(|tag| name.contains(tag))
+    };
+    let host = platform()
+        .filter(|asset| has_tag(asset, host_tags))
+        .collect::<Vec<_>>();
+    if !host.is_empty() {
+        return (host, true);
+    }
+    (
+        platform()
+            .filter(|asset| !has_tag(asset, host_tags) && !has_tag(asset, other_tags))
+            .collect(),
+        false,
+    )
+}
+
 pub fn find_platform_version(asset: &octocrab::models::repos::Asset) -> bool {
+    if [".sha256", ".txt", ".sig", ".asc", ".minisig"]
+        .iter()
+        .any(|ext| asset.name.ends_with(ext))
+    {
+        return false;
+    }
     if cfg!(windows) {
         asset.name.contains("win64")
+    } else if cfg!(target_os = "macos") {
+        asset.name.contains("macos")
+            || asset.name.ends_with(".dmg")
+            || asset.name.ends_with(".app.zip")
     } else if cfg!(unix) {
         asset.name.contains("AppImage")
+            || asset.name.ends_with(".tar.gz")
+            || asset.name.ends_with(".tar.xz")
+            || asset.name.ends_with(".7z")
     } else {
         false
     }
@@ -110,6 +1487,8 @@ pub fn find_platform_version(asset: &octocrab::models::repos::Asset) -> bool {
 pub fn downloaded_name() -> String {
     if cfg!(windows) {
         "VoxelEngine.exe".to_string()
+    } else if cfg!(target_os = "macos") {
+        "VoxelEngine.app/Contents/MacOS/VoxelEngine".to_string()
     } else {
         "VoxelEngine.AppImage".to_string()
     }
@@ -123,24 +1502,568 @@ pub fn binary_name() -> String {
     }
 }
 
-pub fn get_versions_path() -> std::path::PathBuf {
+pub fn check_disk_space(required: u64) -> Result<(), LaunchError> {
+    if required == 0 {
+        return Ok(());
+    }
+    let available = match fs2::available_space(get_versions_path()) {
+        Ok(available) => available,
+        Err(err) => {
+            log::warn!("Failed to query free disk space: {}", err);
+            return Ok(());
+        }
+    };
+    if available < required {
+        return Err(LaunchError::DiskSpace {
+            needed: required,
+            available,
+        });
+    }
+    Ok(())
+}
+
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[unit])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+static INSTALL_DIR: Mutex<Option<std::path::PathBuf>> = Mutex::new(None);
+
+pub fn set_install_dir(path: std::path::PathBuf) {
+    *INSTALL_DIR.lock_safe() = Some(path);
+}
+
+/// Keeps tests out of the real install directory
+#[cfg(test)]
+pub fn set_test_install_dir() -> std::path::PathBuf {
+    let path = std::env::temp_dir().join(format!("voxel-launcher-test-{}", std::process::id()));
+    set_install_dir(path.clone());
+    path
+}
+
+pub fn default_install_dir() -> std::path::PathBuf {
+    directories::ProjectDirs::from("org", "InfiniteCoder", "VoxelEngineLauncher")
+        .map_or_else(|| ".".into(), |dirs| dirs.data_dir().to_path_buf())
+}
+
+/// Versions directory used before the install directory became configurable
+pub fn get_legacy_versions_path() -> std::path::PathBuf {
     std::path::Path::new("versions").to_path_buf()
 }
 
+pub fn copy_dir(from: &std::path::Path, to: &std::path::Path) -> std::io::Result<()> {
+    std::fs::create_dir_all(to)?;
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            std::fs::copy(entry.path(), target)?;
+        }
+    }
+    Ok(())
+}
+
+/// Copies new and changed files only, files that exist just in `to` are kept
+pub fn sync_dir(from: &std::path::Path, to: &std::path::Path) -> std::io::Result<usize> {
+    std::fs::create_dir_all(to)?;
+    let mut copied = 0;
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copied += sync_dir(&entry.path(), &target)?;
+            continue;
+        }
+        let source = entry.metadata()?;
+        let unchanged = target.metadata().is_ok_and(|target| {
+            target.len() == source.len()
+                && matches!(
+                    (source.modified(), target.modified()),
+                    (Ok(source), Ok(target)) if source <= target
+                )
+        });
+        if !unchanged {
+            std::fs::copy(entry.path(), target)?;
+            copied += 1;
+        }
+    }
+    Ok(copied)
+}
+
+fn symlink_dir(from: &std::path::Path, to: &std::path::Path) -> std::io::Result<()> {
+    #[cfg(unix)]
+    return std::os::unix::fs::symlink(from, to);
+    // Needs developer mode or admin rights
+    #[cfg(windows)]
+    return std::os::windows::fs::symlink_dir(from, to);
+    #[cfg(not(any(unix, windows)))]
+    return Err(std::io::ErrorKind::Unsupported.into());
+}
+
+/// Makes the shared content packs available at `target`: links it when possible, copies otherwise.
+/// An existing folder is never replaced, the shared packs are copied into it
+pub fn share_content(shared: &std::path::Path, target: &std::path::Path, interface: &Interface) {
+    if !shared.is_dir() {
+        interface.warning(format!("Shared content folder {:?} does not exist", shared));
+        return;
+    }
+    if let Ok(link) = std::fs::read_link(target) {
+        if link.canonicalize().ok() != shared.canonicalize().ok() {
+            interface.warning(format!(
+                "{:?} links to {:?}, not to the shared content folder, leaving it as is",
+                target, link
+            ));
+        }
+        return;
+    }
+    if !target.exists() {
+        match symlink_dir(shared, target) {
+            Ok(()) => {
+                interface.info(format!("Linked {:?} to {:?}", target, shared));
+                return;
+            }
+            Err(err) => interface.info(format!(
+                "Couldn't link the shared content folder ({}), copying it instead",
+                err
+            )),
+        }
+    }
+    match sync_dir(shared, target) {
+        Ok(0) => (),
+        Ok(copied) => interface.info(format!(
+            "Copied {} changed file(s) from the shared content folder",
+            copied
+        )),
+        Err(err) => interface.warning(format!("Failed to copy the shared content: {}", err)),
+    }
+}
+
+pub fn is_executable(path: &std::path::Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        path.metadata()
+            .is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+    }
+    #[cfg(not(unix))]
+    {
+        path.is_file()
+            && path
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("exe"))
+    }
+}
+
+pub fn migrate_versions(from: &std::path::Path, interface: &Arc<Interface>) {
+    let to = get_versions_path();
+    if let Err(err) = std::fs::create_dir_all(&to) {
+        interface.error(format!("Failed to create {:?}: {}", to, err));
+        return;
+    }
+    let Ok(dir) = std::fs::read_dir(from) else {
+        return;
+    };
+    for entry in dir.flatten() {
+        let target = to.join(entry.file_name());
+        if target.exists() {
+            interface.warning(format!("{:?} already exists, skipping", target));
+        } else if let Err(err) = std::fs::rename(entry.path(), &target) {
+            interface.error(format!("Failed to move {:?}: {}", entry.path(), err));
+        }
+    }
+    if std::fs::remove_dir(from).is_ok() {
+        interface.info(format!("Moved versions to {:?}", to));
+    }
+}
+
+pub fn get_install_dir() -> std::path::PathBuf {
+    INSTALL_DIR
+        .lock_safe()
+        .clone()
+        .unwrap_or_else(default_install_dir)
+}
+
+pub fn get_versions_path() -> std::path::PathBuf {
+    get_install_dir().join("versions")
+}
+
+pub fn unix_time() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs())
+}
+
+/// Names of imported versions and profiles become folder names. Leading dots are taken by the
+/// launcher's own folders (e.g. `.luajit`), or hide the folder
+pub fn valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with('.')
+        && !name.contains(['/', '\\', ':', '*', '?', '"', '<', '>', '|'])
+}
+
 pub fn get_version_path(name: &str) -> std::path::PathBuf {
     get_versions_path().join(name)
 }
 
+pub fn get_log_path() -> std::path::PathBuf {
+    get_versions_path().join("launcher.log")
+}
+
+pub fn open_folder(path: &std::path::Path, interface: &Arc<Interface>) {
+    let opener = if cfg!(windows) {
+        "explorer"
+    } else if cfg!(target_os = "macos") {
+        "open"
+    } else {
+        "xdg-open"
+    };
+    std::fs::create_dir_all(path).ok();
+    let path = path.canonicalize().unwrap_or_else(|_| path.to_owned());
+    // Explorer doesn't understand verbatim "\\?\" paths
+    let path = match path.to_string_lossy().strip_prefix(r"\\?\") {
+        Some(stripped) => std::path::PathBuf::from(stripped),
+        None => path.clone(),
+    };
+    if let Err(err) = std::process::Command::new(opener).arg(&path).spawn() {
+        interface.error(format!("Failed to open {:?}: {}", path, err));
+    }
+}
+
+/// For paths that end up in CMake or make, where backslashes are escapes
+pub fn forward_slashes(path: &std::path::Path) -> String {
+    let path = path.to_string_lossy();
+    path.strip_prefix(r"\\?\")
+        .unwrap_or(&path)
+        .replace('\\', "/")
+}
+
+/// For paths in make variables, make pastes them into shell commands without quotes
+pub fn make_path(path: &std::path::Path) -> String {
+    let mut escaped = String::new();
+    for c in forward_slashes(path).chars() {
+        match c {
+            // make turns $$ into $, the backslash is for the shell
+            '$' => escaped.push_str("\\$$"),
+            c if c.is_whitespace() || "'\"`()[]{}&;|<>*?!#~".contains(c) => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+static LUA_DIR: Mutex<Option<std::path::PathBuf>> = Mutex::new(None);
+
+pub fn set_lua_dir(path: Option<std::path::PathBuf>) {
+    *LUA_DIR.lock_safe() = path;
+}
+
 pub fn get_lua_path() -> std::path::PathBuf {
-    home::home_dir().unwrap().join(".luajit")
+    LUA_DIR
+        .lock_safe()
+        .clone()
+        .unwrap_or_else(|| get_versions_path().join(".luajit"))
+}
+
+/// Where Lua was installed before its location became configurable
+pub fn get_legacy_lua_path() -> Option<std::path::PathBuf> {
+    home::home_dir().map(|home| home.join(".luajit"))
 }
 
-pub fn spawn(f: impl Future<Output = ()> + Send + 'static) {
-    static RUNTIME: Mutex<Option<tokio::runtime::Runtime>> = Mutex::new(None);
+pub fn migrate_lua(interface: &Arc<Interface>) -> bool {
+    let Some(legacy) = get_legacy_lua_path() else {
+        return false;
+    };
+    let target = get_lua_path();
+    if let Some(parent) = target.parent() {
+        std::fs::create_dir_all(parent).ok();
+    }
+    match std::fs::rename(&legacy, &target) {
+        Ok(()) => {
+            interface.info(format!("Moved Lua to {:?}", target));
+            true
+        }
+        Err(err) => {
+            interface.error(format!(
+                "Failed to move Lua to {:?}: {}. You can keep using the old folder instead",
+                target, err
+            ));
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Release assets with these names, each platform has its own kind of download
+    fn assets(tags: &[&str]) -> Vec<octocrab::models::repos::Asset> {
+        let suffix = if cfg!(windows) {
+            "win64.zip"
+        } else if cfg!(target_os = "macos") {
+            "macos.dmg"
+        } else {
+            "AppImage"
+        };
+        tags.iter()
+            .enumerate()
+            .map(|(id, tag)| {
+                let name = if tag.is_empty() {
+                    format!("VoxelEngine-{}", suffix)
+                } else {
+                    format!("VoxelEngine-{}-{}", tag, suffix)
+                };
+                let url = format!("https://example.com/{}", name);
+                serde_json::from_value(serde_json::json!({
+                    "url": url,
+                    "browser_download_url": url,
+                    "id": id,
+                    "node_id": "",
+                    "name": name,
+                    "label": null,
+                    "state": "uploaded",
+                    "content_type": "application/octet-stream",
+                    "size": 0,
+                    "download_count": 0,
+                    "created_at": "2024-01-01T00:00:00Z",
+                    "updated_at": "2024-01-01T00:00:00Z",
+                    "uploader": null,
+                }))
+                .unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn folder_names() {
+        assert!(valid_name("0.20 modded"));
+        for name in [
+            "", ".", "..", ".luajit", "a/b", "a\\b", "C:", "what?", "<a>", "a|b",
+        ] {
+            assert!(!valid_name(name), "{:?}", name);
+        }
+    }
+
+    fn chosen_url(tags: &[&str], arch: &str) -> Option<(String, bool)> {
+        let assets = assets(tags);
+        select_arch_asset(&assets, arch)
+            .map(|(asset, specific)| (asset.browser_download_url.to_string(), specific))
+    }
+
+    #[test]
+    #[cfg_attr(windows, ignore = "every Windows download is tagged win64")]
+    fn arch_asset_for_aarch64() {
+        let (url, specific) = chosen_url(&["x86_64", "aarch64"], "aarch64").unwrap();
+        assert!(url.contains("-aarch64-"), "{}", url);
+        assert!(specific);
+        let (url, specific) = chosen_url(&["amd64", "arm64"], "aarch64").unwrap();
+        assert!(url.contains("-arm64-"), "{}", url);
+        assert!(specific);
+    }
+
+    #[test]
+    #[cfg_attr(windows, ignore = "every Windows download is tagged win64")]
+    fn arch_asset_for_x86_64() {
+        let (url, specific) = chosen_url(&["aarch64", "x86_64"], "x86_64").unwrap();
+        assert!(url.contains("-x86_64-"), "{}", url);
+        assert!(specific);
+        // Never an ARM build, even if it's the only one
+        assert_eq!(chosen_url(&["arm64"], "x86_64"), None);
+    }
+
+    #[test]
+    #[cfg_attr(windows, ignore = "every Windows download is tagged win64")]
+    fn arch_asset_falls_back_to_generic() {
+        let (url, specific) = chosen_url(&["x86_64", ""], "aarch64").unwrap();
+        assert_eq!(
+            url,
+            assets(&[""])[0].browser_download_url.to_string(),
+            "expected the generic build"
+        );
+        assert!(!specific);
+        assert_eq!(chosen_url(&["x86_64"], "aarch64"), None);
+    }
+
+    #[tokio::test]
+    async fn download_fails_on_status() {
+        use std::io::{Read, Write};
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                let mut request = Vec::new();
+                let mut buffer = [0; 1024];
+                while !request.windows(4).any(|end| end == b"\r\n\r\n") {
+                    match stream.read(&mut buffer) {
+                        Ok(0) | Err(_) => break,
+                        Ok(read) => request.extend_from_slice(&buffer[..read]),
+                    }
+                }
+                let body = "\n  Not   Found\n";
+                write!(
+                    stream,
+                    "HTTP/1.1 404 Not Found\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                )
+                .ok();
+            }
+        });
+
+        let (interface, _sink) = sink::TestSink::interface(tokio::runtime::Handle::current());
+        let dir = get_install_dir().join("download_fails_on_status");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("binary");
+        std::fs::remove_file(&path).ok();
+        // A leftover from an earlier attempt
+        std::fs::write(part_path(&path), b"partial").unwrap();
+
+        let url = format!("http://{}/missing", address);
+        let err = download(&url, &path, &interface, "the binary")
+            .await
+            .unwrap_err();
+        match err {
+            LaunchError::Download {
+                error: DownloadError::Status { status, body },
+                ..
+            } => {
+                assert_eq!(status, reqwest::StatusCode::NOT_FOUND);
+                assert_eq!(body, "Not Found");
+            }
+            err => panic!("expected a status error, got {}", err),
+        }
+        assert!(!path.exists());
+        assert_eq!(std::fs::read(part_path(&path)).unwrap(), b"partial");
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn tar_symlinks_stay_inside() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let (interface, _sink) = sink::TestSink::interface(runtime.handle().clone());
+        let dir = get_install_dir().join("tar_symlinks_stay_inside");
+        std::fs::remove_dir_all(&dir).ok();
+        let (version, outside) = (dir.join("version"), dir.join("outside"));
+        std::fs::create_dir_all(&outside).unwrap();
 
-    let mut runtime = RUNTIME.lock().unwrap();
-    if runtime.is_none() {
-        *runtime = Some(tokio::runtime::Runtime::new().unwrap());
+        let mut builder = tar::Builder::new(Vec::new());
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Symlink);
+        header.set_size(0);
+        builder.append_link(&mut header, "a", &outside).unwrap();
+        let mut header = tar::Header::new_gnu();
+        header.set_size(4);
+        header.set_mode(0o644);
+        builder
+            .append_data(&mut header, "a/evil", &b"evil"[..])
+            .unwrap();
+        let tar = builder.into_inner().unwrap();
+
+        let result = extract_tar(
+            || Ok(std::io::Cursor::new(tar.clone())),
+            &version,
+            Toplevel::Keep,
+            &interface,
+        );
+        assert!(result.is_err());
+        assert!(!outside.join("evil").exists());
+    }
+
+    #[test]
+    fn app_bundle_executable_from_plist() {
+        let plist = r#"<?xml version="1.0" encoding="UTF-8"?>
+<plist version="1.0">
+<dict>
+	<key>CFBundleName</key>
+	<string>VoxelEngine</string>
+	<key>CFBundleExecutable</key>
+	<string>VoxelCore</string>
+</dict>
+</plist>"#;
+        assert_eq!(bundle_executable(plist).as_deref(), Some("VoxelCore"));
+        assert_eq!(bundle_executable("<plist><dict></dict></plist>"), None);
+        let escaping = "<key>CFBundleExecutable</key><string>../../evil</string>";
+        assert_eq!(bundle_executable(escaping), None);
+    }
+
+    fn zip_file(dir: &str, files: &[&str]) -> std::path::PathBuf {
+        use std::io::Write;
+        let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        for file in files {
+            zip.start_file(*file, zip::write::FileOptions::default())
+                .unwrap();
+            zip.write_all(file.as_bytes()).unwrap();
+        }
+        let bytes = zip.finish().unwrap().into_inner();
+        let dir = get_install_dir().join(dir);
+        std::fs::remove_dir_all(&dir).ok();
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("archive.zip");
+        std::fs::write(&path, bytes).unwrap();
+        path
+    }
+
+    #[test]
+    fn zip_strips_single_root() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let (interface, _sink) = sink::TestSink::interface(runtime.handle().clone());
+        let archive = zip_file(
+            "zip_strips_single_root",
+            &[
+                "VoxelEngine-abc/CMakeLists.txt",
+                "VoxelEngine-abc/src/main.cpp",
+            ],
+        );
+        let target = archive.with_file_name("unpacked");
+        extract_zip(&archive, &target, Toplevel::Strip, &interface).unwrap();
+        assert!(target.join("CMakeLists.txt").is_file());
+        assert!(target.join("src").join("main.cpp").is_file());
+        assert!(!target.join("VoxelEngine-abc").exists());
+    }
+
+    #[test]
+    fn zip_with_several_roots() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let (interface, _sink) = sink::TestSink::interface(runtime.handle().clone());
+        let archive = zip_file(
+            "zip_with_several_roots",
+            &["a/CMakeLists.txt", "b/main.cpp"],
+        );
+        let target = archive.with_file_name("unpacked");
+        assert!(extract_zip(&archive, &target, Toplevel::Strip, &interface).is_err());
+        // Nothing to strip, everything is kept as it is
+        extract_zip(&archive, &target, Toplevel::StripIfShared, &interface).unwrap();
+        assert!(target.join("a").join("CMakeLists.txt").is_file());
+        assert!(target.join("b").join("main.cpp").is_file());
+    }
+
+    #[test]
+    fn lock_safe_survives_poisoning() {
+        let mutex = Arc::new(Mutex::new(vec![1, 2]));
+        let poisoner = mutex.clone();
+        let result = std::thread::spawn(move || {
+            let mut data = poisoner.lock().unwrap();
+            data.push(3);
+            panic!("poisoning the mutex");
+        })
+        .join();
+        assert!(result.is_err());
+        assert!(mutex.is_poisoned());
+        assert_eq!(*mutex.lock_safe(), vec![1, 2, 3]);
     }
-    runtime.as_ref().unwrap().spawn(f);
 }
@@ -6,21 +6,201 @@ pub enum VersionData {
     Binary {
         url: String,
         unzip: bool,
+        #[serde(default)]
+        checksum_url: Option<String>,
+        /// Detached minisign signature of the asset
+        #[serde(default)]
+        signature_url: Option<String>,
+        #[serde(default)]
+        size: u64,
+        #[serde(default)]
+        asset: String,
+        /// When the asset was last uploaded, to notice re-published releases
+        #[serde(default)]
+        updated_at: Option<String>,
     },
     Source {
         zipball_url: String,
+        #[serde(default)]
+        updated_at: Option<String>,
     },
     Local {
         binary: std::path::PathBuf,
         origin: Box<VersionData>,
+        /// Of the binary right after installing, to detect corruption later
+        #[serde(default)]
+        sha256: Option<String>,
+        /// Unix time of the last launch
+        #[serde(default)]
+        last_played: Option<u64>,
     },
     NotFound,
 }
 
+impl VersionData {
+    fn binary(asset: &AssetInfo, checksum_url: Option<String>) -> Self {
+        Self::Binary {
+            unzip: cfg!(windows)
+                || (cfg!(target_os = "macos") && asset.url.ends_with(".zip"))
+                || utils::archive_extension(&asset.url) != "zip",
+            url: asset.url.clone(),
+            checksum_url,
+            signature_url: asset.signature_url.clone(),
+            size: asset.size,
+            asset: asset.name.clone(),
+            updated_at: asset.updated_at.clone(),
+        }
+    }
+
+    /// Whether `remote` is a newer upload of the release this version was installed from
+    fn outdated_by(&self, remote: &VersionData) -> bool {
+        let VersionData::Local { origin, .. } = self else {
+            return false;
+        };
+        match (origin.as_ref(), remote) {
+            (
+                VersionData::Binary {
+                    url, updated_at, ..
+                },
+                VersionData::Binary {
+                    url: remote_url,
+                    updated_at: remote_updated_at,
+                    ..
+                },
+            ) => {
+                url != remote_url
+                    || updated_at.is_some()
+                        && remote_updated_at.is_some()
+                        && updated_at != remote_updated_at
+            }
+            (
+                VersionData::Source {
+                    updated_at: Some(updated_at),
+                    ..
+                },
+                VersionData::Source {
+                    updated_at: Some(remote_updated_at),
+                    ..
+                },
+            ) => updated_at != remote_updated_at,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AssetInfo {
+    pub name: String,
+    pub url: String,
+    pub size: u64,
+    pub updated_at: Option<String>,
+    pub signature_url: Option<String>,
+}
+
+/// Layout of version.ron. 1 was the bare `VersionData`, older files are migrated when loaded
+pub const SCHEMA_VERSION: u32 = 2;
+
+/// The contents of version.ron
+#[derive(Serialize, Deserialize)]
+struct VersionFile {
+    schema_version: u32,
+    data: VersionData,
+}
+
+impl VersionData {
+    /// None without a version.ron, an error when it isn't in any known layout
+    pub fn load(path: &std::path::Path) -> Option<Result<Self, String>> {
+        let text = std::fs::read_to_string(path).ok()?;
+        Some(Self::parse_file(&text).map(|(data, schema_version)| {
+            if schema_version < SCHEMA_VERSION {
+                // So it's only migrated once. Newer layouts are left alone for the newer launcher
+                if let Err(err) = data.save(path) {
+                    log::warn!("Failed to migrate {:?}: {}", path, err);
+                }
+            }
+            data
+        }))
+    }
+
+    /// The data and the schema version it was written with
+    fn parse_file(text: &str) -> Result<(Self, u32), String> {
+        match ron::from_str::<VersionFile>(text) {
+            Ok(file) => Ok(Self::migrate(file)),
+            Err(err) => ron::from_str::<VersionData>(text)
+                .map(|data| (data, 1))
+                .map_err(|_| err.to_string()),
+        }
+    }
+
+    /// Upgrades data written with an older schema, nothing changed in the data itself so far
+    fn migrate(file: VersionFile) -> (Self, u32) {
+        (file.data, file.schema_version)
+    }
+
+    pub fn save(&self, path: &std::path::Path) -> Result<(), String> {
+        let file = VersionFile {
+            schema_version: SCHEMA_VERSION,
+            data: self.clone(),
+        };
+        let text = ron::to_string(&file).map_err(|err| err.to_string())?;
+        std::fs::write(path, text).map_err(|err| err.to_string())
+    }
+}
+
+/// What the game writes next to itself: worlds, settings, content packs and screenshots
+pub const USER_DATA: [&str; 6] = [
+    "worlds",
+    "content",
+    "screenshots",
+    "export",
+    "settings.toml",
+    "controls.toml",
+];
+
+/// Why a version has nothing to install, see `Version::explain`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NotFoundReason {
+    /// Imported or only found on disk, there is no release to get it from
+    #[default]
+    NoRelease,
+    /// Nothing for this platform, and no source code to build
+    NoPlatformAsset,
+    /// Prebuilt versions are turned off in settings, and there is no source code to build
+    PrebuiltDisabled,
+}
+
+impl std::fmt::Display for NotFoundReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NoRelease => f.write_str("there is no release to download it from"),
+            Self::NoPlatformAsset => write!(
+                f,
+                "the release has no download for {} {} and no source code",
+                std::env::consts::OS,
+                std::env::consts::ARCH
+            ),
+            Self::PrebuiltDisabled => {
+                f.write_str("prebuilt versions are turned off and the release has no source code")
+            }
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct Version {
     pub name: String,
     pub data: Arc<Mutex<VersionData>>,
+    pub notes: Option<String>,
+    pub prerelease: bool,
+    /// Platform assets to choose from, only filled when there is more than one
+    pub assets: Vec<AssetInfo>,
+    checksum_url: Option<String>,
+    /// Fresh release data, when it differs from what is installed
+    pub update: Arc<Mutex<Option<VersionData>>>,
+    /// Why the release data is `NotFound`, when it is
+    pub not_found: NotFoundReason,
+    /// The platform assets aren't built for this architecture, warned about when installing
+    pub arch_fallback: bool,
 }
 
 impl PartialEq for Version {
@@ -30,338 +210,1544 @@ impl PartialEq for Version {
 }
 
 impl Version {
+    pub fn new(name: impl Into<String>, data: VersionData) -> Self {
+        Self {
+            name: name.into(),
+            data: Arc::new(Mutex::new(data)),
+            notes: None,
+            prerelease: false,
+            assets: Vec::new(),
+            checksum_url: None,
+            update: Arc::new(Mutex::new(None)),
+            not_found: NotFoundReason::default(),
+            arch_fallback: false,
+        }
+    }
+
     pub fn parse(
         release: octocrab::models::repos::Release,
         interface: Arc<Interface>,
     ) -> Option<Self> {
-        let name = release.name?;
-        let source = if let Ok(Ok(version_data)) =
-            std::fs::read_to_string(utils::get_version_path(&name).join("version.ron"))
-                .map(|version_data| ron::from_str::<VersionData>(&version_data))
-        {
-            version_data
-        } else if let Some(binary_url) = release
+        // Automated releases often only have a tag
+        let name = release
+            .name
+            .filter(|name| !name.trim().is_empty())
+            .unwrap_or_else(|| release.tag_name.clone());
+        if name.trim().is_empty() {
+            return None;
+        }
+        let version_file = utils::get_version_path(&name).join("version.ron");
+        let local = VersionData::load(&version_file);
+        let corrupted = matches!(local, Some(Err(_)));
+        let (assets, arch_specific) = if interface.config().use_prebuilt_when_possible {
+            utils::select_arch_assets(&release.assets, std::env::consts::ARCH)
+        } else {
+            (Vec::new(), true)
+        };
+        let assets = assets
+            .into_iter()
+            .map(|asset| AssetInfo {
+                name: asset.name.clone(),
+                url: asset.browser_download_url.to_string(),
+                size: asset.size.max(0) as u64,
+                updated_at: Some(asset.updated_at.to_rfc3339()),
+                signature_url: release
+                    .assets
+                    .iter()
+                    .find(|signature| signature.name == format!("{}.minisig", asset.name))
+                    .map(|signature| signature.browser_download_url.to_string()),
+            })
+            .collect::<Vec<_>>();
+        let arch_fallback = !arch_specific && !assets.is_empty();
+        let checksum_url = release
             .assets
             .iter()
-            .find(|asset| utils::find_platform_version(asset))
-            .map(|asset| asset.browser_download_url.to_string())
-            .and_then(|asset| {
-                if interface.config().use_prebuilt_when_possible {
-                    Some(asset)
-                } else {
-                    None
-                }
-            })
+            .find(|asset| asset.name.ends_with(".sha256") || asset.name.contains("checksums"))
+            .map(|asset| asset.browser_download_url.to_string());
+        let chosen = VersionSettings::load_named(&name).asset;
+        let not_found = if interface.config().use_prebuilt_when_possible {
+            NotFoundReason::NoPlatformAsset
+        } else {
+            NotFoundReason::PrebuiltDisabled
+        };
+        let remote = if let Some(asset) = assets
+            .iter()
+            .find(|asset| chosen.as_ref() == Some(&asset.name))
+            .or(assets.first())
         {
-            VersionData::Binary {
-                url: binary_url,
-                unzip: cfg!(windows),
-            }
+            VersionData::binary(asset, checksum_url.clone())
         } else if let Some(zipball_url) = release.zipball_url.map(|url| url.to_string()) {
-            VersionData::Source { zipball_url }
+            VersionData::Source {
+                zipball_url,
+                updated_at: release.published_at.map(|date| date.to_rfc3339()),
+            }
         } else {
             VersionData::NotFound
         };
+        let mut update = None;
+        let source = if let Some(Ok(version_data)) = local {
+            if version_data.outdated_by(&remote) {
+                update = Some(remote);
+            }
+            version_data
+        } else {
+            remote
+        };
+        let version = Self::new(name, source.clone());
+        if corrupted {
+            if let Some(version_data) = version.recover(source) {
+                *version.data.lock_safe() = version_data;
+            }
+        }
         Some(Self {
-            name,
-            data: Arc::new(Mutex::new(source)),
+            notes: release.body.filter(|body| !body.trim().is_empty()),
+            prerelease: release.prerelease,
+            assets: if assets.len() > 1 { assets } else { Vec::new() },
+            checksum_url,
+            update: Arc::new(Mutex::new(update)),
+            not_found,
+            arch_fallback,
+            ..version
         })
     }
 
-    pub fn play(&self, interface: Arc<Interface>, force_refresh: bool) {
-        if force_refresh {
-            let mut data = self.data.lock().unwrap();
-            if let VersionData::Local { origin, .. } = &*data {
-                *data = origin.as_ref().clone();
+    /// What keeps the version from being installed and what could be done about it, if anything.
+    /// True when a setting would fix it
+    pub fn explain(&self, config: &LauncherConfig) -> Option<(String, bool)> {
+        let data = self.data.lock_safe().clone();
+        match data {
+            VersionData::NotFound => Some(match self.not_found {
+                NotFoundReason::NoRelease => (
+                    "This version isn't released on GitHub (anymore), so it can't be downloaded. Import the game binary or choose another version".to_owned(),
+                    false,
+                ),
+                NotFoundReason::NoPlatformAsset => (
+                    format!(
+                        "The release has no download for your platform ({} {}) and no source code to build. Choose another version, or use it on a platform the release supports",
+                        std::env::consts::OS,
+                        std::env::consts::ARCH
+                    ),
+                    false,
+                ),
+                NotFoundReason::PrebuiltDisabled => (
+                    "The release has no source code and prebuilt versions are turned off. Turn on \"Use prebuilt versions when possible\" in settings".to_owned(),
+                    true,
+                ),
+            }),
+            VersionData::Source { .. } if !config.build_unsupported => Some((
+                "There is no prebuilt binary for your platform, only source code. Turn on \"Build unsupported versions from source\" in settings to build it".to_owned(),
+                true,
+            )),
+            VersionData::GitLatest if !config.build_unsupported => Some((
+                "The latest version is built from source. Turn on \"Build unsupported versions from source\" in settings to build it".to_owned(),
+                true,
+            )),
+            _ => None,
+        }
+    }
+
+    /// Whether the user has to pick one of several platform assets before downloading
+    pub fn needs_asset_choice(&self) -> bool {
+        !self.assets.is_empty()
+            && matches!(*self.data.lock_safe(), VersionData::Binary { .. })
+            && VersionSettings::load(self).asset.is_none()
+    }
+
+    pub fn choose_asset(&self, asset: &AssetInfo, interface: &Arc<Interface>) {
+        let mut settings = VersionSettings::load(self);
+        settings.asset = Some(asset.name.clone());
+        settings.save(self, interface);
+        let mut data = self.data.lock_safe();
+        if matches!(*data, VersionData::Binary { .. }) {
+            *data = VersionData::binary(asset, self.checksum_url.clone());
+        }
+    }
+
+    /// Copies a binary or a folder with one into the versions directory, as an installed version
+    pub fn import(source: &std::path::Path, name: &str, interface: &Arc<Interface>) -> bool {
+        let version = Self::new(name, VersionData::NotFound);
+        let path = version.path();
+        let result = if source.is_dir() {
+            let Some(binary) = utils::find_executable(source) else {
+                interface.error(format!("No {} found in {:?}", utils::binary_name(), source));
+                return false;
+            };
+            utils::copy_dir(source, &path).map(|_| binary)
+        } else if utils::is_executable(source) {
+            let binary = std::path::PathBuf::from(source.file_name().unwrap_or_default());
+            std::fs::create_dir_all(&path)
+                .and_then(|_| std::fs::copy(source, path.join(&binary)))
+                .map(|_| binary)
+        } else {
+            interface.error(format!("{:?} is not an executable", source));
+            return false;
+        };
+        let binary = match result {
+            Ok(binary) => binary,
+            Err(err) => {
+                interface.error(format!("Failed to import {:?}: {}", source, err));
+                return false;
             }
+        };
+        let data = VersionData::Local {
+            sha256: utils::sha256_file(&path.join(&binary)).ok(),
+            binary,
+            origin: Box::new(VersionData::NotFound),
+            last_played: None,
+        };
+        if let Err(err) = data.save(&path.join("version.ron")) {
+            interface.error(format!("Failed to import {:?}: {}", source, err));
+            return false;
+        }
+        interface.info(format!("Imported version {}", name));
+        true
+    }
+
+    pub fn update_available(&self) -> bool {
+        self.update.lock_safe().is_some()
+    }
+
+    /// Reinstalls from the fresh release data
+    pub fn apply_update(&self, interface: Arc<Interface>) {
+        let Some(update) = self.update.lock_safe().take() else {
+            return;
+        };
+        if let VersionData::Local { origin, .. } = &mut *self.data.lock_safe() {
+            **origin = update;
         }
+        self.play(interface, true);
+    }
 
+    pub fn play(&self, interface: Arc<Interface>, force_refresh: bool) {
         let this = self.clone();
-        std::fs::create_dir_all(this.path()).ok();
-        let data = this.data.lock().unwrap().clone();
+        interface.clone().spawn(async move {
+            if let Err(err) = this.launch(interface.clone(), force_refresh).await {
+                interface.report(&err);
+            }
+        });
+    }
+
+    pub async fn launch(
+        self,
+        interface: Arc<Interface>,
+        force_refresh: bool,
+    ) -> Result<(), LaunchError> {
+        self.prepare(interface, force_refresh, true).await
+    }
+
+    /// Downloads or builds the version without starting the game
+    pub async fn install(
+        self,
+        interface: Arc<Interface>,
+        force_refresh: bool,
+    ) -> Result<(), LaunchError> {
+        self.prepare(interface, force_refresh, false).await
+    }
+
+    async fn prepare(
+        self,
+        interface: Arc<Interface>,
+        force_refresh: bool,
+        run: bool,
+    ) -> Result<(), LaunchError> {
+        let result = self.prepare_steps(&interface, force_refresh, run).await;
+        if result.is_err() {
+            interface.end_progress(false);
+        }
+        result
+    }
+
+    async fn prepare_steps(
+        &self,
+        interface: &Arc<Interface>,
+        force_refresh: bool,
+        run: bool,
+    ) -> Result<(), LaunchError> {
+        if force_refresh {
+            self.reset();
+        }
+
+        interface.reset_cancel();
+        interface.set_steps(0);
+        let data = self.data.lock_safe().clone();
+        utils::check_tools(&Self::required_tools(&data, interface))?;
+        let offline = interface.config().offline_mode;
+        let available_offline = match data {
+            VersionData::Local { .. } => true,
+            VersionData::GitLatest => self.path().join("src").exists(),
+            _ => false,
+        };
+        if offline && !available_offline {
+            return Err(LaunchError::Offline);
+        }
+        std::fs::create_dir_all(self.path()).ok();
+        utils::check_disk_space(Self::required_space(&data))?;
         match data {
             VersionData::GitLatest => {
                 if !interface.config().build_unsupported {
-                    interface.error("This version has to be built from source");
-                    interface.progress().take();
-                    return;
+                    return Err(LaunchError::Unsupported(
+                        "This version has to be built from source".to_owned(),
+                    ));
                 }
-                utils::spawn(async move {
-                    interface.replace_progress(0.0);
-                    if !this.path().join("src").exists() {
-                        interface.info("Cloning the repo");
-                        let success = utils::run_command(
-                            "git",
-                            &[
-                                "clone",
-                                "https://github.com/MihailRis/VoxelEngine-Cpp",
-                                this.path().to_string_lossy().as_ref(),
-                            ],
-                            None,
-                            &interface,
-                            |_| (),
-                        )
-                        .await;
-                        if !success {
-                            interface.progress().take();
-                            return;
-                        }
-                    } else {
-                        interface.info("Pulling changes from github");
-                        let success = utils::run_command(
-                            "git",
-                            &["pull"],
-                            Some(&this.path()),
-                            &interface,
-                            |_| (),
-                        )
-                        .await;
-                        if !success {
-                            interface.info(
-                                "Failed to clone the repo. Running the latest local commit instead",
-                            );
-                        }
-                    }
+                interface.set_steps(3);
+                interface.next_stage(Stage::Cloning);
+                self.sync_repo(interface).await?;
+                self.build(interface, force_refresh).await?;
 
-                    if !this.build(&interface, force_refresh).await {
-                        interface.progress().take();
-                        return;
-                    }
-
-                    interface.progress().take();
-                    this.run_binary(&interface);
-                });
+                interface.end_progress(true);
+                if run {
+                    self.run_binary(interface).await?;
+                }
+                Ok(())
             }
-            VersionData::Binary { url, unzip } => {
-                utils::spawn(async move {
-                    interface.replace_progress(0.0);
-                    interface.info("Downloading version binary");
+            VersionData::Binary {
+                url,
+                unzip,
+                checksum_url,
+                signature_url,
+                size,
+                ..
+            } => {
+                let dmg = url.ends_with(".dmg");
+                // Unpacking and finishing get their own steps, so the bar doesn't sit at 100%
+                interface.set_steps(if unzip || dmg { 3 } else { 2 });
+                interface.next_stage(Stage::Downloading);
+                if self.arch_fallback {
+                    utils::warn_arch_fallback(url.rsplit('/').next().unwrap_or(&url), interface);
+                }
+                interface.info("Downloading version binary");
 
-                    let bytes = match utils::download(&url, &interface, "binary").await {
-                        Some(bytes) => bytes,
-                        None => {
-                            interface.progress().take();
-                            return;
-                        }
-                    };
-                    if unzip {
-                        if !utils::unpack(&bytes, &this.path(), &interface) {
-                            interface.progress().take();
-                            return;
-                        }
-                    } else {
-                        let mut binfile = File::create(this.downloaded_path()).unwrap();
-                        std::io::copy(&mut std::io::Cursor::new(bytes), &mut binfile).unwrap();
-                        drop(binfile);
+                let archive_dir = if interface.config().keep_archives {
+                    self.path().join("cache")
+                } else {
+                    self.path()
+                };
+                let target = if unzip {
+                    archive_dir.join(format!("binary.{}", utils::archive_extension(&url)))
+                } else if dmg {
+                    archive_dir.join("binary.dmg")
+                } else {
+                    self.downloaded_path()
+                };
+                Self::fetch_mirrored(&url, size, &target, checksum_url.as_deref(), interface)
+                    .await?;
+                if interface.config().verify_signatures {
+                    let verified =
+                        utils::verify_signature(&target, signature_url.as_deref(), interface).await;
+                    if verified.is_err() {
+                        std::fs::remove_file(&target).ok();
                     }
+                    verified?;
+                }
+                if unzip || dmg {
+                    interface.next_stage(Stage::Unpacking);
+                }
+                // App bundles have to keep their top-level "*.app" directory
+                let toplevel = if cfg!(target_os = "macos") {
+                    utils::Toplevel::Keep
+                } else {
+                    utils::Toplevel::StripIfShared
+                };
+                let unpacked = if unzip {
+                    utils::unpack(&target, &self.path(), toplevel, interface).await
+                } else if dmg {
+                    utils::unpack_dmg(&target, &self.path(), interface).await
+                } else {
+                    Ok(())
+                };
+                if unzip || dmg {
+                    utils::remove_archive(&target, interface);
+                }
+                unpacked?;
 
-                    #[cfg(target_os = "linux")]
-                    {
-                        use std::os::unix::fs::PermissionsExt;
-                        std::fs::set_permissions(
-                            this.downloaded_path(),
-                            std::fs::Permissions::from_mode(0o755),
-                        )
-                        .unwrap();
-                    }
+                // Archives keep their own permissions
+                #[cfg(target_os = "linux")]
+                if !unzip {
+                    use std::os::unix::fs::PermissionsExt;
+                    std::fs::set_permissions(
+                        self.downloaded_path(),
+                        std::fs::Permissions::from_mode(0o755),
+                    )
+                    .map_err(|err| {
+                        LaunchError::Other(format!(
+                            "Failed to make {:?} executable: {}",
+                            self.downloaded_path(),
+                            err
+                        ))
+                    })?;
+                }
 
-                    this.finish(utils::downloaded_name(), &interface);
-                });
+                interface.next_stage(Stage::Finishing);
+                let binary = if cfg!(target_os = "macos") {
+                    utils::find_app_binary(&self.path())
+                        .unwrap_or_else(|| utils::downloaded_name().into())
+                } else if cfg!(target_os = "linux") && unzip {
+                    utils::find_executable(&self.path()).ok_or_else(|| {
+                        LaunchError::Other("Game executable not found in the archive".to_owned())
+                    })?
+                } else {
+                    utils::downloaded_name().into()
+                };
+                self.finish(binary, interface, run).await
             }
-            VersionData::Source { zipball_url } => {
+            VersionData::Source { zipball_url, .. } => {
                 if !interface.config().build_unsupported {
-                    interface
-                        .error("This version doesn't have prebuilt binaries for your platform");
-                    interface.progress().take();
-                    return;
+                    return Err(LaunchError::Unsupported(
+                        "This version doesn't have prebuilt binaries for your platform".to_owned(),
+                    ));
                 }
                 if self.name == "v11" || self.name == "v12" {
-                    interface.error("Versions 0.11 and 0.12 are not supported by the laucher");
-                    return;
+                    return Err(LaunchError::Unsupported(
+                        "Versions 0.11 and 0.12 are not supported by the laucher".to_owned(),
+                    ));
                 }
 
-                utils::spawn(async move {
-                    interface.replace_progress(0.0);
-                    interface.info("Downloading version source");
+                interface.set_steps(4);
+                interface.next_stage(Stage::Downloading);
+                interface.info("Downloading version source");
+
+                let archive = if interface.config().keep_archives {
+                    self.path().join("cache").join("source.zip")
+                } else {
+                    self.path().join("source.zip")
+                };
+                Self::fetch(&zipball_url, &archive, None, interface, "zipball").await?;
+
+                interface.next_stage(Stage::Unpacking);
+                interface.info("Unpacking version sources");
+                let unpacked =
+                    utils::unpack(&archive, &self.path(), utils::Toplevel::Strip, interface).await;
+                utils::remove_archive(&archive, interface);
+                unpacked?;
+                self.build(interface, force_refresh).await?;
+
+                let binary = self.built_binary(interface);
+                self.finish(binary, interface, run).await
+            }
+            VersionData::Local { .. } if !run => {
+                interface.info(format!("{} is already installed", self.name));
+                Ok(())
+            }
+            VersionData::Local { .. } => self.run_binary(interface).await,
+            VersionData::NotFound => Err(LaunchError::NotFound(self.not_found)),
+        }
+    }
 
-                    let bytes = match utils::download(&zipball_url, &interface, "zipball").await {
-                        Some(bytes) => bytes,
+    /// Tries the mirrors from settings before `url`. A mirror is only trusted when its file matches
+    /// the release checksum, or at least the size of the asset when the release has no checksums
+    async fn fetch_mirrored(
+        url: &str,
+        size: u64,
+        target: &std::path::Path,
+        checksum_url: Option<&str>,
+        interface: &Arc<Interface>,
+    ) -> Result<(), LaunchError> {
+        let (mirrors, verify) = {
+            let config = interface.config();
+            (
+                config.mirrors().map(str::to_owned).collect::<Vec<_>>(),
+                config.verify_downloads,
+            )
+        };
+        let cached = interface.config().keep_archives && target.exists();
+        if !cached && !mirrors.is_empty() {
+            let asset = url.rsplit('/').next().unwrap_or_default();
+            for mirror in &mirrors {
+                interface.info(format!("Trying mirror {}", mirror));
+                let mut result = utils::download(
+                    &format!("{}/{}", mirror, asset),
+                    target,
+                    interface,
+                    "binary",
+                )
+                .await;
+                if result.is_ok() {
+                    result = match checksum_url {
+                        Some(checksum_url) => {
+                            utils::verify_checksum(target, url, checksum_url, interface).await
+                        }
                         None => {
-                            interface.progress().take();
-                            return;
+                            let actual = std::fs::metadata(target).map_or(0, |meta| meta.len());
+                            if size == 0 || actual == size {
+                                Ok(())
+                            } else {
+                                Err(LaunchError::Verification(format!(
+                                    "expected {} bytes, got {}",
+                                    size, actual
+                                )))
+                            }
                         }
                     };
-
-                    interface.info("Unpacking version sources");
-                    if !utils::unpack(&bytes, &this.path(), &interface) {
-                        interface.progress().take();
-                        return;
+                }
+                match result {
+                    Ok(()) => {
+                        interface.info(format!("Downloaded the binary from {}", mirror));
+                        return Ok(());
                     }
-                    if !this.build(&interface, force_refresh).await {
-                        interface.progress().take();
-                        return;
+                    Err(LaunchError::Cancelled) => return Err(LaunchError::Cancelled),
+                    Err(err) => {
+                        // Neither may end up in the download from the next source
+                        std::fs::remove_file(target).ok();
+                        std::fs::remove_file(utils::part_path(target)).ok();
+                        interface.warning(format!("Mirror {} failed: {}", mirror, err));
                     }
-
-                    this.finish(
-                        std::path::Path::new("build").join(utils::binary_name()),
-                        &interface,
-                    );
-                });
-            }
-            VersionData::Local { .. } => self.run_binary(&interface),
-            VersionData::NotFound => {
-                interface.error("Version files not found or it's not supported on your platform");
+                }
             }
         }
+        let checksum_url = checksum_url.filter(|_| verify);
+        Self::fetch(url, target, checksum_url, interface, "binary").await?;
+        if !cached && !mirrors.is_empty() {
+            interface.info("Downloaded the binary from GitHub");
+        }
+        Ok(())
     }
 
-    pub fn path(&self) -> std::path::PathBuf {
-        utils::get_version_path(&self.name)
+    /// Downloads into `target`, unless a copy kept by `keep_archives` is there and intact
+    async fn fetch(
+        url: &str,
+        target: &std::path::Path,
+        checksum_url: Option<&str>,
+        interface: &Arc<Interface>,
+        name: &str,
+    ) -> Result<(), LaunchError> {
+        if interface.config().keep_archives && target.exists() {
+            interface.info(format!("Using the cached {}", name));
+            match checksum_url {
+                // A mismatching copy gets deleted and downloaded again below
+                Some(checksum_url) => {
+                    match utils::verify_checksum(target, url, checksum_url, interface).await {
+                        Ok(()) => return Ok(()),
+                        Err(err) => interface.warning(err.to_string()),
+                    }
+                }
+                None => return Ok(()),
+            }
+        }
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent).ok();
+        }
+        utils::download(url, target, interface, name).await?;
+        match checksum_url {
+            Some(checksum_url) => {
+                utils::verify_checksum(target, url, checksum_url, interface).await
+            }
+            None => Ok(()),
+        }
     }
 
-    pub fn downloaded_path(&self) -> std::path::PathBuf {
-        self.path().join(utils::downloaded_name())
-    }
+    pub async fn sync_repo(&self, interface: &Arc<Interface>) -> Result<(), LaunchError> {
+        let git_ref = interface.config().git_ref.trim().to_owned();
+        let shallow = interface.config().shallow_clone;
+        let url = interface.config().clone_url();
+        if !self.path().join("src").exists() {
+            interface.info("Cloning the repo");
+            let path = self.path().to_string_lossy().to_string();
+            let mut args = vec!["clone", "--progress", &url, &path];
+            if shallow {
+                args.extend(["--depth", "1"]);
+            }
+            let cloned = utils::run_command_with(
+                "git",
+                &args,
+                None,
+                interface,
+                |_| (),
+                |line| utils::git_progress(line, interface),
+            )
+            .await;
+            if cloned.is_err() {
+                // A half-cloned repo would make the next clone fail
+                std::fs::remove_dir_all(self.path()).ok();
+            }
+            cloned?;
+        } else if interface.config().offline_mode {
+            interface.info("Offline mode, building the local copy of the repo");
+        } else {
+            // The Lua patch is a local change to a tracked file, git won't pull over it. The build
+            // applies it again to whatever comes in
+            self.patch_cmake_lists(false, interface);
+            // The upstream repo may have been changed in settings
+            if let Err(err) = utils::run_command(
+                "git",
+                &["remote", "set-url", "origin", &url],
+                Some(&self.path()),
+                interface,
+                |_| (),
+            )
+            .await
+            {
+                interface.warning(err.to_string());
+            }
 
-    pub async fn build(&self, interface: &Arc<Interface>, force_refresh: bool) -> bool {
-        if interface.config().download_lua {
-            if !utils::get_lua_path().join("lib").exists() {
-                std::fs::remove_dir_all(utils::get_lua_path()).ok();
-                std::fs::create_dir_all(utils::get_lua_path()).unwrap();
-                interface.info("Downloading lua");
-                let success = utils::run_command(
+            if !shallow && self.path().join(".git").join("shallow").exists() {
+                interface.info("Fetching full history");
+                let fetched = utils::run_command_with(
                     "git",
-                    &[
-                        "clone",
-                        "https://luajit.org/git/luajit.git",
-                        utils::get_lua_path().to_string_lossy().as_ref(),
-                    ],
-                    None,
+                    &["fetch", "--progress", "--unshallow"],
+                    Some(&self.path()),
                     interface,
                     |_| (),
+                    |line| utils::git_progress(line, interface),
                 )
                 .await;
-                if !success {
-                    return false;
+                if let Err(err) = fetched {
+                    interface.warning(format!(
+                        "Failed to fetch full history, the clone stays shallow: {}",
+                        err
+                    ));
                 }
+            }
 
-                interface.info("Building lua");
-                let success = utils::run_command(
-                    "make",
-                    &[],
-                    Some(&utils::get_lua_path()),
-                    interface,
-                    |_| (),
+            // A pinned ref may be a detached HEAD, which can't be pulled
+            let (message, mut args) = if git_ref.is_empty() {
+                ("Pulling changes from github", vec!["pull", "--progress"])
+            } else {
+                (
+                    "Fetching changes from github",
+                    vec!["fetch", "--progress", "origin"],
                 )
-                .await;
-                if !success {
-                    return false;
+            };
+            if shallow {
+                args.extend(["--depth", "1"]);
+            }
+            interface.info(message);
+            let pulled = utils::run_command_with(
+                "git",
+                &args,
+                Some(&self.path()),
+                interface,
+                |_| (),
+                |line| utils::git_progress(line, interface),
+            )
+            .await;
+            match pulled {
+                Err(LaunchError::Cancelled) => return Err(LaunchError::Cancelled),
+                Err(err) => {
+                    interface.info(format!("{}. Running the latest local commit instead", err))
                 }
+                Ok(()) => (),
+            }
+        }
 
-                let success = utils::run_command(
-                    "make",
-                    &[
-                        "install",
-                        &format!("PREFIX={:?}", utils::get_lua_path().join("lib")),
-                    ],
-                    Some(&utils::get_lua_path()),
-                    interface,
-                    |_| (),
-                )
-                .await;
-                if !success {
-                    return false;
+        if !git_ref.is_empty() {
+            interface.info(format!("Checking out {}", git_ref));
+            utils::run_command(
+                "git",
+                &["checkout", &git_ref],
+                Some(&self.path()),
+                interface,
+                |_| (),
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Describes what `launch` would do, without doing any of it
+    pub fn plan(&self, interface: &Arc<Interface>, force_refresh: bool) -> Vec<String> {
+        let config = interface.config().clone();
+        let settings = VersionSettings::load(self);
+        let path = self.path();
+        let mut plan = Vec::new();
+        let mut data = self.data.lock_safe().clone();
+        if force_refresh {
+            if let VersionData::Local { origin, .. } = data {
+                plan.push("Forget the installed files (force refresh)".to_owned());
+                data = *origin;
+            }
+        }
+
+        let build_type = settings.build_type(&config).as_str();
+        let build = |plan: &mut Vec<String>| {
+            if config.download_lua && !utils::get_lua_path().join("lib").exists() {
+                plan.push(format!(
+                    "Download and build LuaJIT in {:?}",
+                    utils::get_lua_path()
+                ));
+            }
+            if force_refresh {
+                plan.push(format!("Remove {:?}", path.join("build")));
+            }
+            let mut configure = format!("cmake -DCMAKE_BUILD_TYPE={} -Bbuild", build_type);
+            let extra_args = settings.cmake_extra_args(&config);
+            if let Ok([_, generator]) = config.cmake_generator.args().as_deref() {
+                if !extra_args.iter().any(|arg| arg.starts_with("-G")) {
+                    configure.push_str(&format!(" -G {:?}", generator));
                 }
             }
-            if let Ok(cmake) = std::fs::read_to_string(self.path().join("CMakeLists.txt")) {
-                let lua_path = utils::get_lua_path().join("lib").canonicalize().unwrap();
-                std::fs::write(self.path().join("CMakeLists.txt"), cmake.replace(
-                    "find_package(Lua REQUIRED)",
-                    &format!(
-                        "include_directories(\"{}/include/luajit-2.1/\")\nset(LUA_LIBRARIES \"{}/lib/libluajit-5.1.a\")",
-                        lua_path.to_string_lossy().as_ref(), lua_path.to_string_lossy().as_ref()
-                    ),
-                )).unwrap();
+            let compiler_cache = config.compiler_cache
+                && !extra_args
+                    .iter()
+                    .any(|arg| arg.contains("_COMPILER_LAUNCHER"));
+            if let Some(tool) = compiler_cache.then(utils::compiler_cache).flatten() {
+                configure.push_str(&format!(
+                    " -DCMAKE_C_COMPILER_LAUNCHER={} -DCMAKE_CXX_COMPILER_LAUNCHER={}",
+                    tool, tool
+                ));
             }
+            for arg in extra_args {
+                configure.push(' ');
+                configure.push_str(&arg);
+            }
+            plan.push(configure);
+            plan.push(format!(
+                "cmake --build build --config {} --parallel {}",
+                build_type,
+                config.jobs()
+            ));
+            let post_build = config.post_build_command.trim();
+            if !post_build.is_empty() {
+                plan.push(format!("Run {:?} in {:?}", post_build, path.join("build")));
+            }
+        };
+        match &data {
+            VersionData::GitLatest => {
+                if !path.join("src").exists() {
+                    plan.push(format!(
+                        "git clone {} {:?}{}",
+                        config.clone_url(),
+                        path,
+                        if config.shallow_clone {
+                            " --depth 1"
+                        } else {
+                            ""
+                        }
+                    ));
+                } else if config.offline_mode {
+                    plan.push("Use the local copy of the repo (offline mode)".to_owned());
+                } else if config.git_ref.trim().is_empty() {
+                    plan.push(format!("git pull from {}", config.clone_url()));
+                } else {
+                    plan.push(format!("git fetch origin from {}", config.clone_url()));
+                }
+                if !config.git_ref.trim().is_empty() {
+                    plan.push(format!("git checkout {}", config.git_ref.trim()));
+                }
+                build(&mut plan);
+            }
+            VersionData::Binary {
+                url,
+                unzip,
+                checksum_url,
+                size,
+                ..
+            } => {
+                let mirrors = config.mirrors().count();
+                if mirrors > 0 {
+                    plan.push(format!("Try {} download mirrors first", mirrors));
+                }
+                plan.push(format!("Download {} ({})", url, utils::format_size(*size)));
+                if checksum_url.is_some() && config.verify_downloads {
+                    plan.push("Verify the SHA-256 checksum".to_owned());
+                }
+                if *unzip {
+                    plan.push(format!("Unzip to {:?}", path));
+                } else if url.ends_with(".dmg") {
+                    plan.push(format!("Copy the app from the disk image to {:?}", path));
+                }
+                plan.push("Mark as installed".to_owned());
+            }
+            VersionData::Source { zipball_url, .. } => {
+                plan.push(format!("Download sources from {}", zipball_url));
+                plan.push(format!("Unzip to {:?}", path));
+                build(&mut plan);
+                plan.push("Mark as installed".to_owned());
+            }
+            VersionData::Local { binary, .. } => {
+                plan.push(format!("Use the installed {:?}", path.join(binary)));
+            }
+            VersionData::NotFound => {
+                plan.push(format!("Nothing, {}", self.not_found));
+                return plan;
+            }
+        }
+        plan.push(format!(
+            "Run the game with arguments {:?}",
+            utils::split_args(&settings.launch_args(&config))
+        ));
+        plan
+    }
+
+    fn required_tools(data: &VersionData, interface: &Arc<Interface>) -> Vec<&'static str> {
+        let mut tools = match data {
+            VersionData::GitLatest => vec!["git", "cmake"],
+            VersionData::Source { .. } => vec!["cmake"],
+            _ => return Vec::new(),
+        };
+        if interface.config().download_lua && !utils::get_lua_path().join("lib").exists() {
+            for tool in ["git", "make"] {
+                if !tools.contains(&tool) {
+                    tools.push(tool);
+                }
+            }
+        }
+        tools
+    }
+
+    /// Rough estimate, archives need room for both the archive and its contents
+    fn required_space(data: &VersionData) -> u64 {
+        const SOURCE_BUILD_SIZE: u64 = 1024 * 1024 * 1024;
+        match data {
+            VersionData::Binary {
+                size, unzip: true, ..
+            } => size * 3,
+            VersionData::Binary { url, size, .. } if url.ends_with(".dmg") => size * 3,
+            VersionData::Binary { size, .. } => *size,
+            VersionData::GitLatest | VersionData::Source { .. } => SOURCE_BUILD_SIZE,
+            _ => 0,
         }
+    }
+
+    /// Checks an installed version without running it, returns the problems found
+    pub fn verify(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        let data = self.data.lock_safe().clone();
+        let (binary, sha256) = match &data {
+            VersionData::Local { binary, sha256, .. } => (self.path().join(binary), sha256.clone()),
+            _ => return vec!["The version is not installed".to_owned()],
+        };
+        if !binary.exists() {
+            problems.push(format!("The game executable {:?} is missing", binary));
+            return problems;
+        }
+        if !utils::is_executable(&binary) {
+            problems.push(format!("{:?} is not executable", binary));
+        }
+        if let Some(expected) = sha256 {
+            match utils::sha256_file(&binary) {
+                Ok(actual) if actual != expected => problems.push(format!(
+                    "{:?} was modified or corrupted since installing",
+                    binary
+                )),
+                Ok(_) => (),
+                Err(err) => problems.push(format!("Failed to read {:?}: {}", binary, err)),
+            }
+        }
+        if let VersionData::Local { origin, .. } = &data {
+            if matches!(
+                **origin,
+                VersionData::Source { .. } | VersionData::GitLatest
+            ) && !self.path().join("build").is_dir()
+            {
+                problems.push("The build directory is missing".to_owned());
+            }
+        }
+        problems
+    }
+
+    /// Installs the version again with force refresh, without starting the game
+    pub fn repair(&self, interface: Arc<Interface>) {
+        let this = self.clone();
+        interface.clone().spawn(async move {
+            match this.clone().install(interface.clone(), true).await {
+                Ok(()) => interface.info(format!("Repaired {}", this.name)),
+                Err(err) => interface.report(&err),
+            }
+        });
+    }
+
+    pub fn installed(&self) -> bool {
+        matches!(*self.data.lock_safe(), VersionData::Local { .. })
+    }
+
+    pub fn reset(&self) {
+        let mut data = self.data.lock_safe();
+        if let VersionData::Local { origin, .. } = &*data {
+            *data = origin.as_ref().clone();
+        }
+    }
+
+    pub fn delete(&self, interface: &Arc<Interface>) {
+        if let Err(err) = std::fs::remove_dir_all(self.path()) {
+            interface.error(format!("Failed to delete version {}: {}", self.name, err));
+            return;
+        }
+        self.reset();
+        interface.info(format!("Deleted version {}", self.name));
+    }
+
+    /// Deletes the version directory and installs it from scratch. The version settings and notes
+    /// stay, and with `keep_user_data` also whatever `USER_DATA` lists and the profiles
+    pub fn reinstall(&self, keep_user_data: bool, interface: Arc<Interface>) {
+        let origin = match &*self.data.lock_safe() {
+            VersionData::Local { origin, .. } => origin.as_ref().clone(),
+            data => data.clone(),
+        };
+        if origin == VersionData::NotFound {
+            interface.report(&LaunchError::NotFound(self.not_found));
+            return;
+        }
+        let mut keep = vec!["settings.ron", "notes.ron"];
+        if keep_user_data {
+            keep.extend(USER_DATA);
+            keep.push("profiles");
+        }
+        // Kept downloads are what makes reinstalling cheap
+        if interface.config().keep_archives {
+            keep.push("cache");
+        }
+        // A custom working directory inside the version folder holds the user data itself
+        let working_dir = VersionSettings::load(self).working_dir(self, &interface.config());
+        let working_dir = working_dir
+            .strip_prefix(self.path())
+            .ok()
+            .and_then(|dir| dir.components().next())
+            .map(|dir| dir.as_os_str().to_string_lossy().into_owned());
+        let this = self.clone();
+        interface.clone().spawn(async move {
+            interface.info(format!("Deleting the files of {}", this.name));
+            let path = this.path();
+            let wiped = tokio::task::spawn_blocking(move || {
+                let dir = match std::fs::read_dir(&path) {
+                    Ok(dir) => dir,
+                    Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+                    Err(err) => return Err(err),
+                };
+                for entry in dir.flatten() {
+                    let name = entry.file_name().to_string_lossy().into_owned();
+                    if keep.contains(&name.as_str())
+                        || (keep_user_data && working_dir.as_ref() == Some(&name))
+                    {
+                        continue;
+                    }
+                    if entry.file_type()?.is_dir() {
+                        std::fs::remove_dir_all(entry.path())?;
+                    } else {
+                        std::fs::remove_file(entry.path())?;
+                    }
+                }
+                Ok(())
+            })
+            .await
+            .unwrap_or_else(|err| Err(std::io::Error::other(err)));
+            if let Err(err) = wiped {
+                interface.error(format!(
+                    "Failed to delete the files of {}: {}",
+                    this.name, err
+                ));
+                return;
+            }
+            *this.data.lock_safe() = origin;
+            if let Err(err) = this.install(interface.clone(), false).await {
+                interface.report(&err);
+            }
+        });
+    }
+
+    pub fn path(&self) -> std::path::PathBuf {
+        utils::get_version_path(&self.name)
+    }
+
+    pub fn profiles_path(&self) -> std::path::PathBuf {
+        self.path().join("profiles")
+    }
+
+    pub fn profiles(&self) -> Vec<String> {
+        let mut profiles = std::fs::read_dir(self.profiles_path())
+            .map(|dir| {
+                dir.flatten()
+                    .filter(|entry| entry.path().is_dir())
+                    .map(|entry| entry.file_name().to_string_lossy().into_owned())
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        profiles.sort();
+        profiles
+    }
+
+    pub fn create_profile(&self, name: &str, interface: &Arc<Interface>) -> bool {
+        let name = name.trim();
+        if !utils::valid_name(name) {
+            interface.error(format!("{:?} can't be used as a profile name", name));
+            return false;
+        }
+        let path = self.profiles_path().join(name);
+        if path.exists() {
+            interface.error(format!("Profile {:?} already exists", name));
+            return false;
+        }
+        if let Err(err) = std::fs::create_dir_all(&path) {
+            interface.error(format!("Failed to create profile {:?}: {}", name, err));
+            return false;
+        }
+        true
+    }
+
+    pub fn delete_profile(&self, name: &str, interface: &Arc<Interface>) {
+        match std::fs::remove_dir_all(self.profiles_path().join(name)) {
+            Ok(()) => interface.info(format!("Deleted profile {:?}", name)),
+            Err(err) => interface.error(format!("Failed to delete profile {:?}: {}", name, err)),
+        }
+    }
+
+    pub fn downloaded_path(&self) -> std::path::PathBuf {
+        self.path().join(utils::downloaded_name())
+    }
+
+    pub async fn build(
+        &self,
+        interface: &Arc<Interface>,
+        force_refresh: bool,
+    ) -> Result<(), LaunchError> {
+        interface.next_stage(Stage::Configuring);
+        let download_lua = interface.config().download_lua;
+        if download_lua && !utils::get_lua_path().join("lib").exists() {
+            std::fs::remove_dir_all(utils::get_lua_path()).ok();
+            std::fs::create_dir_all(utils::get_lua_path()).map_err(|err| {
+                LaunchError::Other(format!(
+                    "Failed to create {:?}: {}",
+                    utils::get_lua_path(),
+                    err
+                ))
+            })?;
+            interface.info("Downloading lua");
+            utils::run_command(
+                "git",
+                &[
+                    "clone",
+                    "https://luajit.org/git/luajit.git",
+                    utils::get_lua_path().to_string_lossy().as_ref(),
+                ],
+                None,
+                interface,
+                |_| (),
+            )
+            .await?;
+
+            interface.info("Building lua");
+            utils::run_command("make", &[], Some(&utils::get_lua_path()), interface, |_| ())
+                .await?;
+
+            let args = lua_install_args(&utils::get_lua_path());
+            utils::run_command(
+                "make",
+                &args.iter().map(String::as_str).collect::<Vec<_>>(),
+                Some(&utils::get_lua_path()),
+                interface,
+                |_| (),
+            )
+            .await?;
+        }
+        self.patch_cmake_lists(download_lua, interface);
 
         interface.info("Building the game");
+        let settings = VersionSettings::load(self);
+        let build_type = settings.build_type(&interface.config()).as_str();
+        let mut args = vec![
+            format!("-DCMAKE_BUILD_TYPE={}", build_type),
+            "-Bbuild".to_owned(),
+        ];
+        let extra_args = settings.cmake_extra_args(&interface.config());
+        // A -G in the extra arguments wins
+        if !extra_args.iter().any(|arg| arg.starts_with("-G")) {
+            let generator = interface.config().cmake_generator;
+            match generator.args() {
+                Ok(generator) => args.extend(generator),
+                Err(err) => interface.warning(format!(
+                    "Can't use the {} generator, {}. Using CMake's default",
+                    generator.as_str(),
+                    err
+                )),
+            }
+        }
+        let compiler_cache = interface.config().compiler_cache
+            && !extra_args
+                .iter()
+                .any(|arg| arg.contains("_COMPILER_LAUNCHER"));
+        let compiler_cache = if compiler_cache {
+            let tool = utils::compiler_cache();
+            if tool.is_none() {
+                interface.warning(
+                    "The compiler cache is turned on, but neither ccache nor sccache was found in PATH",
+                );
+            }
+            tool
+        } else {
+            None
+        };
+        if let Some(tool) = compiler_cache {
+            args.push(format!("-DCMAKE_C_COMPILER_LAUNCHER={}", tool));
+            args.push(format!("-DCMAKE_CXX_COMPILER_LAUNCHER={}", tool));
+        }
+        args.extend(extra_args);
+        let toolchain = Toolchain::current(&args);
         if force_refresh {
             std::fs::remove_dir_all(self.path().join("build")).ok();
+        } else if let Some(reason) = toolchain.mismatch(self) {
+            interface.warning(format!("Starting from a clean build directory, {}", reason));
+            std::fs::remove_dir_all(self.path().join("build")).ok();
         }
         std::fs::create_dir(self.path().join("build")).ok();
-        let success = utils::run_command(
-            "cmake",
-            &["-DCMAKE_BUILD_TYPE=Release", "-Bbuild"],
-            Some(&self.path()),
-            interface,
-            |_| (),
-        )
-        .await;
-        if !success {
-            return false;
+        let inputs = ConfigureInputs::current(self, &args);
+        if inputs.unchanged(self) {
+            interface.info("The CMake files didn't change, skipping the configure step");
+        } else {
+            let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+            utils::run_command("cmake", &args, Some(&self.path()), interface, |_| ()).await?;
+            toolchain.save(self);
+            inputs.save(self);
         }
 
-        let success = utils::run_command(
+        interface.next_stage(Stage::Compiling);
+        let jobs = interface.config().jobs().to_string();
+        utils::run_command(
             "cmake",
-            &["--build", "build"],
+            &[
+                "--build",
+                "build",
+                "--config",
+                build_type,
+                "--parallel",
+                &jobs,
+            ],
             Some(&self.path()),
             interface,
             |line| {
-                if let Some(percentage) = line
-                    .strip_prefix('[')
-                    .and_then(|line| line.split_once(']'))
-                    .and_then(|(percentage, _)| percentage.trim().strip_suffix('%'))
-                    .and_then(|percentage| percentage.trim().parse::<i32>().ok())
-                {
-                    interface.set_progress(percentage as f32 / 100.0, line);
+                if let Some((progress, label)) = utils::build_progress(line) {
+                    interface.set_progress(progress, label);
                 }
             },
         )
+        .await?;
+
+        if let Some(tool) = compiler_cache {
+            interface.info(format!("{} statistics:", tool));
+            let stats = utils::run_command(tool, &["--show-stats"], None, interface, |line| {
+                interface.push_log(line, LogLevel::Output)
+            })
+            .await;
+            if let Err(err) = stats {
+                interface.warning(err.to_string());
+            }
+        }
+
+        self.post_build(interface).await
+    }
+
+    /// Runs the user's post-build command (e.g. ctest) in the build directory, failing blocks the launch
+    async fn post_build(&self, interface: &Arc<Interface>) -> Result<(), LaunchError> {
+        let command = interface.config().post_build_command.trim().to_owned();
+        let args = utils::split_args(&command);
+        let Some((program, args)) = args.split_first() else {
+            return Ok(());
+        };
+        interface.info(format!("Running the post-build command: {}", command));
+        let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+        let result = utils::run_command(
+            program,
+            &args,
+            Some(&self.path().join("build")),
+            interface,
+            |line| interface.push_log(line, LogLevel::Output),
+        )
         .await;
-        if !success {
-            return false;
+        if let Err(LaunchError::CommandFailed { .. }) = result {
+            interface.error("The post-build command failed, the game won't be started");
         }
+        result
+    }
 
-        true
+    /// Points CMake at the downloaded LuaJIT, or restores the original CMakeLists.txt without it
+    fn patch_cmake_lists(&self, lua: bool, interface: &Arc<Interface>) {
+        let path = self.path().join("CMakeLists.txt");
+        let Ok(live) = std::fs::read_to_string(&path) else {
+            return;
+        };
+        // The patch is undone from the marked block itself, a git pull or a new unpack just
+        // brings an unpatched file
+        let original = unpatch_cmake_lists(&live);
+        if !lua {
+            if live.contains(LUA_PATCH_MARKER) {
+                if let Err(err) = std::fs::write(&path, &original) {
+                    interface.warning(format!("Failed to restore CMakeLists.txt: {}", err));
+                }
+            }
+            return;
+        }
+
+        if !original.contains("find_package(Lua REQUIRED)") {
+            interface
+                .warning("CMakeLists.txt doesn't look for Lua, the downloaded Lua won't be used");
+            return;
+        }
+        // Patched from the original every time, so a moved Lua folder gets picked up
+        let lua_path = utils::get_lua_path().join("lib");
+        let lua_path = utils::forward_slashes(&lua_path.canonicalize().unwrap_or(lua_path));
+        let patched = patch_cmake_lists(&original, &lua_path);
+        if patched != live {
+            if let Err(err) = std::fs::write(&path, patched) {
+                interface.warning(format!("Failed to patch CMakeLists.txt: {}", err));
+            }
+        }
+    }
+
+    /// Multi-config generators (Visual Studio, Xcode) put binaries into `build/<config>/`
+    pub fn built_binary(&self, interface: &Arc<Interface>) -> std::path::PathBuf {
+        let build = std::path::Path::new("build");
+        let binary = build.join(utils::binary_name());
+        if self.path().join(&binary).exists() {
+            return binary;
+        }
+        let build_type = VersionSettings::load(self)
+            .build_type(&interface.config())
+            .as_str();
+        let binary = build.join(build_type).join(utils::binary_name());
+        if self.path().join(&binary).exists() {
+            return binary;
+        }
+        build.join(utils::binary_name())
+    }
+
+    /// Rebuilds the version data from a binary found on disk, for a lost or corrupted version.ron
+    pub fn recover(&self, origin: VersionData) -> Option<VersionData> {
+        let build = std::path::Path::new("build");
+        let binary = std::iter::once(utils::downloaded_name().into())
+            .chain(std::iter::once(build.join(utils::binary_name())))
+            .chain(
+                BuildType::ALL
+                    .iter()
+                    .map(|build_type| build.join(build_type.as_str()).join(utils::binary_name())),
+            )
+            .find(|binary: &std::path::PathBuf| self.path().join(binary).is_file())?;
+        let data = VersionData::Local {
+            binary,
+            origin: Box::new(origin),
+            sha256: None,
+            last_played: None,
+        };
+        data.save(&self.path().join("version.ron")).ok()?;
+        Some(data)
     }
 
-    pub fn finish(&self, binary: impl AsRef<std::path::Path>, interface: &Arc<Interface>) {
+    pub async fn finish(
+        &self,
+        binary: impl AsRef<std::path::Path>,
+        interface: &Arc<Interface>,
+        run: bool,
+    ) -> Result<(), LaunchError> {
         {
-            let mut data = self.data.lock().unwrap();
+            let mut data = self.data.lock_safe();
+            let last_played = match &*data {
+                VersionData::Local { last_played, .. } => *last_played,
+                _ => None,
+            };
             *data = VersionData::Local {
                 binary: binary.as_ref().to_path_buf(),
                 origin: Box::new(data.clone()),
+                sha256: utils::sha256_file(&self.path().join(binary.as_ref())).ok(),
+                last_played,
             };
 
-            std::fs::write(
-                self.path().join("version.ron"),
-                ron::to_string(&*data).unwrap(),
-            )
-            .unwrap();
+            data.save(&self.path().join("version.ron")).map_err(|err| {
+                LaunchError::Other(format!("Failed to save version.ron: {}", err))
+            })?;
+        }
+
+        interface.end_progress(true);
+        if run {
+            self.run_binary(interface).await?;
+        }
+        Ok(())
+    }
+
+    /// Searches for the executable when the recorded one is gone and records the new path
+    fn relocate_binary(
+        &self,
+        stale: &std::path::Path,
+        interface: &Arc<Interface>,
+    ) -> Option<std::path::PathBuf> {
+        let found = utils::find_executable(&self.path())?;
+        interface.warning(format!("{:?} is missing, using {:?} instead", stale, found));
+        let mut data = self.data.lock_safe();
+        if let VersionData::Local { binary, sha256, .. } = &mut *data {
+            *binary = found.clone();
+            *sha256 = utils::sha256_file(&self.path().join(&found)).ok();
+            self.save_data(&data, interface);
         }
+        Some(found)
+    }
 
-        interface.progress().take();
-        self.run_binary(interface)
+    fn save_data(&self, data: &VersionData, interface: &Arc<Interface>) {
+        if let Err(err) = data.save(&self.path().join("version.ron")) {
+            interface.warning(format!("Failed to save version.ron: {}", err));
+        }
     }
 
-    pub fn run_binary(&self, interface: &Arc<Interface>) {
-        interface.info("Running the game");
-        let binary = match &*self.data.lock().unwrap() {
+    pub fn last_played(&self) -> Option<u64> {
+        match &*self.data.lock_safe() {
+            VersionData::Local { last_played, .. } => *last_played,
+            _ => None,
+        }
+    }
+
+    fn mark_played(&self, interface: &Arc<Interface>) {
+        let mut data = self.data.lock_safe();
+        if let VersionData::Local { last_played, .. } = &mut *data {
+            *last_played = Some(utils::unix_time());
+            self.save_data(&data, interface);
+        }
+    }
+
+    pub async fn run_binary(&self, interface: &Arc<Interface>) -> Result<(), LaunchError> {
+        if interface.game_running() {
+            return Err(LaunchError::GameRunning);
+        }
+        interface.info(tr("Running the game"));
+        let binary = match &*self.data.lock_safe() {
             VersionData::Local { binary, .. } => binary.to_owned(),
-            VersionData::GitLatest => std::path::Path::new("build").join(utils::binary_name()),
-            _ => {
-                interface.error("Error: Binary not found! Use force-refresh");
-                return;
-            }
+            VersionData::GitLatest => self.built_binary(interface),
+            _ => return Err(LaunchError::BinaryNotFound(None)),
+        };
+        let binary = if utils::is_executable(&self.path().join(&binary)) {
+            binary
+        } else if let Some(found) = self.relocate_binary(&binary, interface) {
+            found
+        } else {
+            return Err(LaunchError::BinaryNotFound(Some(binary)));
         };
 
-        if let Err(err) = self.path().join(binary).canonicalize().and_then(|binpath| {
-            std::process::Command::new(binpath)
-                .current_dir(self.path())
-                .spawn()
-        }) {
-            interface.error(format!("Failed to run game executable: {}", err));
+        // The headless launcher exits right away, so the game keeps its own stdout there
+        let capture = interface.config().capture_game_output && !interface.is_headless();
+        let settings = VersionSettings::load(self);
+        let (mut args, env, working_dir, shared_content) = {
+            let config = interface.config();
+            (
+                utils::split_args(&settings.launch_args(&config)),
+                settings.env(&config),
+                settings.working_dir(self, &config),
+                config.shared_content_dir.clone(),
+            )
+        };
+        if let Err(err) = std::fs::create_dir_all(&working_dir) {
+            return Err(LaunchError::Other(format!(
+                "Failed to create {:?}: {}",
+                working_dir, err
+            )));
+        }
+        let mut data_dir = working_dir.clone();
+        if let Some(profile) = &settings.profile {
+            let profile_dir = self.profiles_path().join(profile);
+            if profile_dir.is_dir() {
+                interface.info(format!("Using profile {:?}", profile));
+                // The engine's user directory: worlds, settings and content packs
+                args.splice(
+                    0..0,
+                    [
+                        "--dir".to_owned(),
+                        profile_dir.to_string_lossy().into_owned(),
+                    ],
+                );
+                data_dir = profile_dir;
+            } else {
+                interface.warning(format!(
+                    "Profile {:?} doesn't exist anymore, using the main data",
+                    profile
+                ));
+            }
+        }
+        if let Some(shared) = shared_content.filter(|_| !settings.skip_shared_content) {
+            // The game looks for user content packs in ./content
+            utils::share_content(&shared, &data_dir.join("content"), interface);
         }
+        let binpath = self
+            .path()
+            .join(binary)
+            .canonicalize()
+            .map_err(LaunchError::Game)?;
+        interface.info(format!("Command line: {:?} {:?}", binpath, args));
+        if !env.is_empty() {
+            let env = env
+                .iter()
+                .map(|(name, value)| format!("{}={}", name, value))
+                .collect::<Vec<_>>();
+            interface.info(format!("Environment overrides: {}", env.join(" ")));
+        }
+        if working_dir != self.path() {
+            interface.info(format!("Working directory: {:?}", working_dir));
+        }
+        // Its stderr is watched for FUSE errors even when the output isn't captured
+        let appimage = cfg!(target_os = "linux")
+            && binpath
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("AppImage"));
+        let spawn = |extra_args: &[&str]| {
+            use std::process::Stdio;
+            let mut command = std::process::Command::new(&binpath);
+            command
+                .args(extra_args)
+                .args(&args)
+                .envs(env.iter().map(|(name, value)| (name, value)))
+                .current_dir(&working_dir);
+            if capture {
+                command.stdout(Stdio::piped()).stderr(Stdio::piped());
+            } else if appimage && !interface.is_headless() {
+                // The headless launcher exits right away and would take the pipe with it
+                command.stderr(Stdio::piped());
+            }
+            let mut child = command.spawn()?;
+            let mentioned_fuse = if capture {
+                utils::capture_output(&mut child, interface);
+                None
+            } else {
+                utils::pass_through_stderr(&mut child)
+            };
+            Ok::<_, std::io::Error>((child, mentioned_fuse))
+        };
+        let log_start = interface.log().len();
+        let (child, mentioned_fuse) = spawn(&[]).map_err(LaunchError::Game)?;
+        interface.game().replace(child);
+        self.mark_played(interface);
+        let mentioned_fuse = || match &mentioned_fuse {
+            Some(mentioned) => mentioned.load(Ordering::Relaxed),
+            None => interface
+                .log()
+                .iter()
+                .skip(log_start)
+                .any(|line| line.text.to_lowercase().contains("fuse")),
+        };
+        if appimage && utils::appimage_fuse_failed(interface, mentioned_fuse).await {
+            interface.warning(
+                "The AppImage couldn't be mounted, FUSE is probably missing. Retrying with --appimage-extract-and-run",
+            );
+            let (child, _) = spawn(&["--appimage-extract-and-run"]).map_err(LaunchError::Game)?;
+            interface.game().replace(child);
+        }
+        Ok(())
+    }
+}
+
+/// `make install` into lua_path/lib. LuaJIT's Makefile doesn't quote its install paths, so the
+/// prefix is escaped for the shell instead
+fn lua_install_args(lua_path: &std::path::Path) -> [String; 2] {
+    [
+        "install".to_owned(),
+        format!("PREFIX={}", utils::make_path(&lua_path.join("lib"))),
+    ]
+}
+
+const LUA_PATCH_MARKER: &str = "# Lua paths patched by the VoxelEngine launcher";
+const LUA_PATCH_END: &str = "/lib/libluajit-5.1.a\")";
+
+/// Points `find_package(Lua REQUIRED)` at the downloaded LuaJIT in `lua_path`
+fn patch_cmake_lists(cmake: &str, lua_path: &str) -> String {
+    cmake.replace(
+        "find_package(Lua REQUIRED)",
+        &format!(
+            "{}\ninclude_directories(\"{}/include/luajit-2.1/\")\nset(LUA_LIBRARIES \"{}{}",
+            LUA_PATCH_MARKER, lua_path, lua_path, LUA_PATCH_END
+        ),
+    )
+}
+
+/// Undoes `patch_cmake_lists`, whatever Lua path was put in
+fn unpatch_cmake_lists(cmake: &str) -> String {
+    let mut cmake = cmake.to_owned();
+    while let Some(start) = cmake.find(LUA_PATCH_MARKER) {
+        let Some(end) = cmake[start..].find(LUA_PATCH_END) else {
+            break;
+        };
+        cmake.replace_range(
+            start..start + end + LUA_PATCH_END.len(),
+            "find_package(Lua REQUIRED)",
+        );
+    }
+    cmake
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn version_file_migrates_v1() {
+        // A bare VersionData, as launchers before the schema version wrote it
+        let v1 = r#"Local(binary: "VoxelEngine", origin: Binary(url: "https://example.com/voxelengine.AppImage", unzip: false))"#;
+        let expected = VersionData::Local {
+            binary: "VoxelEngine".into(),
+            origin: Box::new(VersionData::Binary {
+                url: "https://example.com/voxelengine.AppImage".to_owned(),
+                unzip: false,
+                checksum_url: None,
+                signature_url: None,
+                size: 0,
+                asset: String::new(),
+                updated_at: None,
+            }),
+            sha256: None,
+            last_played: None,
+        };
+        assert_eq!(VersionData::parse_file(v1), Ok((expected.clone(), 1)));
+
+        let dir = utils::set_test_install_dir().join("version_file_migrates_v1");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("version.ron");
+        std::fs::write(&path, v1).unwrap();
+        assert_eq!(VersionData::load(&path), Some(Ok(expected.clone())));
+        let saved = std::fs::read_to_string(&path).unwrap();
+        assert!(saved.contains("schema_version:2"), "{}", saved);
+        assert_eq!(
+            VersionData::parse_file(&saved),
+            Ok((expected, SCHEMA_VERSION))
+        );
+    }
+
+    #[test]
+    fn cmake_lua_patch_round_trips() {
+        let original =
+            "project(VoxelEngine)\r\nfind_package(Lua REQUIRED)\r\nadd_executable(game)\r\n";
+        let patched = patch_cmake_lists(original, "C:/Users/Some One/lua/lib");
+        assert!(patched.contains(LUA_PATCH_MARKER));
+        assert!(!patched.contains("find_package(Lua REQUIRED)"));
+        assert_eq!(unpatch_cmake_lists(&patched), original);
+        // A new upstream file without the patch stays as it is
+        assert_eq!(unpatch_cmake_lists(original), original);
+    }
+
+    #[test]
+    fn lua_prefix_survives_spaces_and_backslashes() {
+        let [install, prefix] = lua_install_args(std::path::Path::new(r"C:\Users\Some One\lua"));
+        assert_eq!(install, "install");
+        assert_eq!(prefix, r"PREFIX=C:/Users/Some\ One/lua/lib");
+        let [_, prefix] = lua_install_args(std::path::Path::new("/home/me/My Games/$lua (1)"));
+        assert_eq!(prefix, r"PREFIX=/home/me/My\ Games/\$$lua\ \(1\)/lib");
+    }
+
+    /// A release as the GitHub API returns it, assets are left out
+    fn release(name: Option<&str>, tag_name: &str) -> octocrab::models::repos::Release {
+        let url = "https://example.com/release";
+        serde_json::from_value(serde_json::json!({
+            "url": url,
+            "html_url": url,
+            "assets_url": url,
+            "upload_url": url,
+            "tarball_url": null,
+            "zipball_url": null,
+            "id": 0,
+            "node_id": "",
+            "tag_name": tag_name,
+            "target_commitish": "main",
+            "name": name,
+            "body": null,
+            "draft": false,
+            "prerelease": false,
+            "created_at": null,
+            "published_at": null,
+            "author": null,
+            "assets": [],
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn untitled_release_uses_tag() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let (interface, _sink) = sink::TestSink::interface(runtime.handle().clone());
+        let version = Version::parse(release(None, "v0.20"), interface.clone()).unwrap();
+        assert_eq!(version.name, "v0.20");
+        let version = Version::parse(release(Some("  "), "v0.21"), interface.clone()).unwrap();
+        assert_eq!(version.name, "v0.21");
+        let version = Version::parse(release(Some("Beta"), "v0.22"), interface.clone()).unwrap();
+        assert_eq!(version.name, "Beta");
+        assert!(Version::parse(release(None, ""), interface).is_none());
     }
 }